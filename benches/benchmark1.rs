@@ -8,7 +8,7 @@ fn run_main_loop(config: &csv2bibtex::args::Config) {
 fn criterion_benchmark(c: &mut Criterion) {
     // build config structure
     let mut config = csv2bibtex::args::Config {
-        file_input: std::path::PathBuf::from("./benches/benchmark1-input1.csv"),
+        files_input: vec![std::path::PathBuf::from("./benches/benchmark1-input1.csv")],
         file_output: std::path::PathBuf::from("./benches/tmp-benchmark1-output1.bib"),
         csv_delimiter: String::from("\t"),
         csv_lazy: true,
@@ -102,7 +102,7 @@ fn criterion_benchmark(c: &mut Criterion) {
     });
 
     // switch to 1000 lines input file, clear fields, run again
-    config.file_input = std::path::PathBuf::from("./benches/benchmark1-input2.csv");
+    config.files_input = vec![std::path::PathBuf::from("./benches/benchmark1-input2.csv")];
     config.csv_field_mapping.clear();
     c.bench_function("0 fields, 1000 lines", |b| {
         b.iter(|| run_main_loop(black_box(&config)))
@@ -1,22 +1,17 @@
-use anyhow::anyhow;
+use crate::Error;
 use clap::crate_authors;
 use clap::crate_description;
 use clap::crate_name;
 use clap::crate_version;
 
 /// Output Type (BibTex vs. BibLaTeX)
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub enum OutputType {
     Bibtex,
+    #[default]
     Biblatex,
 }
 
-impl Default for OutputType {
-    fn default() -> Self {
-        Self::Biblatex
-    }
-}
-
 /// Main Config
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -24,12 +19,95 @@ pub struct Config {
     pub csv_field_mapping: std::collections::HashMap<String, String>,
     /// Try to recover from as much errors as possible
     pub csv_lazy: bool,
-    pub file_input: std::path::PathBuf,
+    /// Maximum number of rows that may fail before the conversion is aborted
+    pub max_errors: Option<usize>,
+    /// Maximum fraction of rows that may fail before the conversion is aborted
+    pub max_error_rate: Option<f64>,
+    /// Input files or glob patterns like "exports/*.csv"
+    pub files_input: Vec<std::path::PathBuf>,
     pub file_output: std::path::PathBuf,
+    /// File to write rows that could not be converted to
+    pub file_rejects: Option<std::path::PathBuf>,
+    /// Convert only rows matching this expression, see `filter::Filter`
+    pub filter: Option<String>,
+    /// Number of rows to skip at the beginning of the CSV file
+    pub skip_rows: usize,
+    /// Maximum number of rows to convert
+    pub limit_rows: Option<usize>,
+    /// Print the entries instead of writing the output file
+    pub dry_run: bool,
+    /// Print the columns of the input files instead of converting them, see `inspect`
+    pub inspect: bool,
+    /// Print a profile with the guessed mapping instead of converting, see `suggest`
+    pub print_profile: bool,
+    /// Create a profile interactively instead of converting, see `wizard`
+    pub wizard: bool,
+    /// Guess the mapping of fields that are not mapped otherwise, see `suggest`
+    pub suggest_mapping: bool,
+    /// File to write a report on the conversion to, see `report::Report`
+    pub file_report: Option<std::path::PathBuf>,
+    pub report_format: crate::report::ReportFormat,
+    /// File with a field mapping, see `profile::Profile`
+    pub file_profile: Option<std::path::PathBuf>,
+    /// Field to save the input file of an entry in
+    pub source_field: Option<String>,
+    /// Sort entries by these fields, see `sort::EntrySorter`
+    pub sort_by: Option<String>,
+    /// Write one output file per value of this field, see `split::SplitWriter`
+    pub split_by: Option<String>,
+    /// Merge rows with the same value in this column into one entry
+    pub group_by: Option<String>,
+    /// Merge only consecutive rows with the same value
+    pub group_consecutive: bool,
+    /// Aggregation rules of fields when rows are merged, see `group::Aggregation`
+    pub aggregations: std::collections::HashMap<String, crate::group::Aggregation>,
     pub log_level: log::LevelFilter,
     pub output_type: OutputType,
+    /// Layout of the written entries
+    pub format: crate::bibwriter::Format,
+    /// Fields whose frequent values are replaced by `@string` macros
+    pub string_fields: Vec<String>,
+    /// Minimum number of occurrences of a value to get a macro
+    pub string_min_count: usize,
+    /// File with `@string` macros to use
+    pub file_strings: Option<std::path::PathBuf>,
+    /// Write months as macros like `mar`
+    pub month_macros: bool,
     pub mapping_defaults: bool,
+    /// Copy every CSV column that is not mapped to a field of the same name
+    pub passthrough: bool,
+    /// CSV columns or field names that are not copied by `passthrough`
+    pub passthrough_exclude: Vec<String>,
     pub verbatim_fields: Vec<String>,
+    /// Parse fields ending in "date" and convert them to ISO 8601
+    pub parse_dates: bool,
+    /// Input formats of dates, see `date::DateParser`
+    pub date_formats: Vec<String>,
+    /// Order of day and month in ambiguous numeric dates
+    pub date_order: crate::date::DateOrder,
+    /// Convert non-ASCII characters to LaTeX commands in the output
+    pub latex_encode: bool,
+    /// Convert LaTeX commands in CSV cells to unicode characters
+    pub latex_decode: bool,
+    /// Protect acronyms, camel case words, and `protected_words` in titles
+    pub protect_titles: bool,
+    pub protected_words: Vec<String>,
+    /// Normalize page ranges like "pp. 45 - 67" to "45--67"
+    pub normalize_pages: bool,
+    /// Split keyword lists, remove duplicates, and join them with ", "
+    pub normalize_keywords: bool,
+    /// Separators of keywords, see `keywords::KeywordNormalizer`
+    pub keyword_separators: Vec<String>,
+    /// Convert keywords to lowercase
+    pub lowercase_keywords: bool,
+    /// Normalize and validate DOIs, ISBNs, ISSNs, and URLs
+    pub normalize_identifiers: bool,
+    /// Convert ISBN-10 to ISBN-13
+    pub isbn13: bool,
+    /// Lists of journal titles and their abbreviations, see `journals::JournalList`
+    pub journal_lists: Vec<std::path::PathBuf>,
+    /// Form of journal titles found in `journal_lists`
+    pub journal_form: crate::journals::JournalForm,
 }
 
 impl Default for Config {
@@ -38,43 +116,128 @@ impl Default for Config {
             csv_delimiter: String::from(","),
             csv_field_mapping: std::collections::HashMap::new(),
             csv_lazy: false,
-            file_input: std::path::PathBuf::new(),
+            max_errors: None,
+            max_error_rate: None,
+            files_input: std::vec::Vec::new(),
             file_output: std::path::PathBuf::new(),
+            file_rejects: None,
+            filter: None,
+            skip_rows: 0,
+            limit_rows: None,
+            dry_run: false,
+            inspect: false,
+            print_profile: false,
+            wizard: false,
+            suggest_mapping: false,
+            file_profile: None,
+            file_report: None,
+            report_format: crate::report::ReportFormat::default(),
+            source_field: None,
+            sort_by: None,
+            split_by: None,
+            group_by: None,
+            group_consecutive: false,
+            aggregations: std::collections::HashMap::new(),
             log_level: log::LevelFilter::Info,
             output_type: OutputType::default(),
+            format: crate::bibwriter::Format::default(),
+            string_fields: std::vec::Vec::new(),
+            string_min_count: 3,
+            file_strings: None,
+            month_macros: false,
             mapping_defaults: true,
+            passthrough: false,
+            passthrough_exclude: std::vec::Vec::new(),
             verbatim_fields: std::vec::Vec::new(),
+            parse_dates: false,
+            date_formats: std::vec::Vec::new(),
+            date_order: crate::date::DateOrder::default(),
+            latex_encode: false,
+            latex_decode: false,
+            protect_titles: false,
+            protected_words: std::vec::Vec::new(),
+            normalize_pages: false,
+            normalize_keywords: false,
+            keyword_separators: std::vec::Vec::new(),
+            lowercase_keywords: false,
+            normalize_identifiers: false,
+            isbn13: false,
+            journal_lists: std::vec::Vec::new(),
+            journal_form: crate::journals::JournalForm::default(),
         }
     }
 }
 
 impl Config {
-    pub fn new() -> Result<Self, anyhow::Error> {
+    pub fn new() -> Result<Self, Error> {
         let matches = clap::Command::new(crate_name!())
             .version(crate_version!())
             .about(crate_description!())
             .author(crate_authors!())
             .arg(
-                clap::Arg::new("input-file")
-                    .help("Input file to use")
+                clap::Arg::new("files")
+                    .help("Input files or glob patterns like \"exports/*.csv\", then the output file unless --output is given")
                     .takes_value(true)
-                    .value_name("INPUT")
+                    .multiple_values(true)
+                    .min_values(1)
+                    .value_name("FILES")
                     .required(true)
                     .index(1),
             )
             .arg(
-                clap::Arg::new("output-file")
-                    .help("Output file to use")
+                clap::Arg::new("output")
+                    .help("Output file, all FILES are input files then")
+                    .long("output")
+                    .short('o')
                     .takes_value(true)
-                    .value_name("OUTPUT")
-                    .required(true)
-                    .index(2),
+                    .value_name("FILE"),
+            )
+            .subcommand_negates_reqs(true)
+            .subcommand(
+                clap::Command::new("inspect")
+                    .about("Print the columns, fill rates, sample values, and a guessed mapping of CSV files")
+                    .arg(
+                        clap::Arg::new("files")
+                            .help("Input files or glob patterns like \"exports/*.csv\"")
+                            .takes_value(true)
+                            .multiple_values(true)
+                            .value_name("FILES")
+                            .required(true)
+                            .index(1),
+                    ),
+            )
+            .subcommand(
+                clap::Command::new("wizard")
+                    .about("Create a mapping profile for a CSV file interactively")
+                    .arg(
+                        clap::Arg::new("files")
+                            .help("Input file or glob pattern like \"exports/*.csv\"")
+                            .takes_value(true)
+                            .multiple_values(true)
+                            .value_name("FILES")
+                            .required(true)
+                            .index(1),
+                    ),
+            )
+            .subcommand(
+                clap::Command::new("suggest")
+                    .about("Print a mapping profile with the guessed mapping of a CSV file")
+                    .arg(
+                        clap::Arg::new("files")
+                            .help("Input file or glob pattern like \"exports/*.csv\"")
+                            .takes_value(true)
+                            .multiple_values(true)
+                            .value_name("FILES")
+                            .required(true)
+                            .index(1),
+                    ),
             )
             .arg(
                 clap::Arg::new("log-level")
                     .help("Verbosity level, either DEBUG, INFO, WARN, or ERROR")
                     .long("verbosity")
                     .short('v')
+                    .global(true)
                     .takes_value(true)
                     .value_name("LEVEL"),
             )
@@ -83,6 +246,7 @@ impl Config {
                     .help("Delimiter between cells in CSV file")
                     .long("delimiter")
                     .short('d')
+                    .global(true)
                     .takes_value(true)
                     .value_name("DELIMITER"),
             )
@@ -109,8 +273,23 @@ impl Config {
                     .help("Try to recover from as much errors as possible.")
                     .long("lazy")
                     .short('l')
+                    .global(true)
                     .takes_value(false),
             )
+            .arg(
+                clap::Arg::new("max-errors")
+                    .help("Skip at most N rows that cannot be converted")
+                    .long("max-errors")
+                    .takes_value(true)
+                    .value_name("N"),
+            )
+            .arg(
+                clap::Arg::new("max-error-rate")
+                    .help("Skip at most this fraction of rows, like 5% or 0.05")
+                    .long("max-error-rate")
+                    .takes_value(true)
+                    .value_name("RATE"),
+            )
             .arg(
                 clap::Arg::new("no-defaults")
                     .help("Don't add default field mappings and verbatim fields.")
@@ -127,6 +306,33 @@ impl Config {
                     .number_of_values(1)
                     .value_name("FIELD"),
             )
+            .arg(
+                clap::Arg::new("report")
+                    .help("Write a report on entry types, filled fields, and skipped rows to a file")
+                    .long("report")
+                    .takes_value(true)
+                    .value_name("FILE"),
+            )
+            .arg(
+                clap::Arg::new("report-format")
+                    .help("Format of the report, either text or json (default: by file extension)")
+                    .long("report-format")
+                    .takes_value(true)
+                    .value_name("FORMAT"),
+            )
+            .arg(
+                clap::Arg::new("profile")
+                    .help("File with a field mapping like \"author = [[Authors]]\" per line")
+                    .long("profile")
+                    .takes_value(true)
+                    .value_name("FILE"),
+            )
+            .arg(
+                clap::Arg::new("suggest-mapping")
+                    .help("Guess the mapping of fields from the column names")
+                    .long("suggest-mapping")
+                    .takes_value(false),
+            )
             .arg(
                 clap::Arg::new("verbatim-field")
                     .help("Bib(La)TeX verbatim fields, like url, file or doi")
@@ -136,20 +342,338 @@ impl Config {
                     .number_of_values(1)
                     .value_name("FIELD"),
             )
+            .arg(
+                clap::Arg::new("parse-dates")
+                    .help("Parse date fields and convert them to ISO 8601 (year/month in BibTeX)")
+                    .long("parse-dates")
+                    .takes_value(false),
+            )
+            .arg(
+                clap::Arg::new("date-format")
+                    .help("Input format of dates, like \"%d.%m.%Y\" (implies --parse-dates)")
+                    .long("date-format")
+                    .takes_value(true)
+                    .multiple_occurrences(true)
+                    .number_of_values(1)
+                    .value_name("FORMAT"),
+            )
+            .arg(
+                clap::Arg::new("date-order")
+                    .help("Order of day and month in numeric dates, either DMY or MDY")
+                    .long("date-order")
+                    .takes_value(true)
+                    .value_name("ORDER"),
+            )
+            .arg(
+                clap::Arg::new("latex-encode")
+                    .help("Convert non-ASCII characters to LaTeX commands, e.g., ü to {\\\"u}")
+                    .long("latex-encode")
+                    .takes_value(false),
+            )
+            .arg(
+                clap::Arg::new("latex-decode")
+                    .help("Convert LaTeX commands in CSV cells to unicode characters")
+                    .long("latex-decode")
+                    .takes_value(false),
+            )
+            .arg(
+                clap::Arg::new("protect-titles")
+                    .help("Protect acronyms and camel case words in titles against case changes")
+                    .long("protect-titles")
+                    .takes_value(false),
+            )
+            .arg(
+                clap::Arg::new("protect-word")
+                    .help("Word to protect in titles, like Bayesian (implies --protect-titles)")
+                    .long("protect-word")
+                    .takes_value(true)
+                    .multiple_occurrences(true)
+                    .number_of_values(1)
+                    .value_name("WORD"),
+            )
+            .arg(
+                clap::Arg::new("rejects-file")
+                    .help("CSV file to write rows to that could not be converted")
+                    .long("rejects")
+                    .takes_value(true)
+                    .value_name("FILE"),
+            )
+            .arg(
+                clap::Arg::new("where")
+                    .help("Convert only rows matching an expression, like \"PY>=2015 && DT=='Article'\"")
+                    .long("where")
+                    .takes_value(true)
+                    .value_name("EXPRESSION"),
+            )
+            .arg(
+                clap::Arg::new("skip")
+                    .help("Skip the first N rows of the CSV file")
+                    .long("skip")
+                    .takes_value(true)
+                    .value_name("N"),
+            )
+            .arg(
+                clap::Arg::new("limit")
+                    .help("Convert at most N rows")
+                    .long("limit")
+                    .takes_value(true)
+                    .value_name("N"),
+            )
+            .arg(
+                clap::Arg::new("dry-run")
+                    .help("Print the entries instead of writing the output file, e.g., with --limit 5")
+                    .long("dry-run")
+                    .takes_value(false),
+            )
+            .arg(
+                clap::Arg::new("passthrough")
+                    .help("Copy every CSV column that is not mapped to a field of the same name")
+                    .long("passthrough")
+                    .takes_value(false),
+            )
+            .arg(
+                clap::Arg::new("passthrough-exclude")
+                    .help("CSV column not to copy (implies --passthrough)")
+                    .long("passthrough-exclude")
+                    .takes_value(true)
+                    .multiple_occurrences(true)
+                    .number_of_values(1)
+                    .value_name("COLUMN"),
+            )
+            .arg(
+                clap::Arg::new("group-by")
+                    .help("Merge rows with the same value in this column into one entry")
+                    .long("group-by")
+                    .takes_value(true)
+                    .value_name("COLUMN"),
+            )
+            .arg(
+                clap::Arg::new("group-consecutive")
+                    .help("Merge only consecutive rows with the same value")
+                    .long("group-consecutive")
+                    .takes_value(false),
+            )
+            .arg(
+                clap::Arg::new("aggregate")
+                    .help("How to merge a field: first, last, or join:SEPARATOR")
+                    .long("aggregate")
+                    .takes_value(true)
+                    .multiple_occurrences(true)
+                    .number_of_values(1)
+                    .value_name("FIELD=RULE"),
+            )
+            .arg(
+                clap::Arg::new("source-field")
+                    .help("Field to save the input file of each entry in")
+                    .long("source-field")
+                    .takes_value(true)
+                    .value_name("FIELD"),
+            )
+            .arg(
+                clap::Arg::new("split-by")
+                    .help("Write one output file per value of a field, like out/{year}.bib")
+                    .long("split-by")
+                    .takes_value(true)
+                    .value_name("FIELD"),
+            )
+            .arg(
+                clap::Arg::new("sort-by")
+                    .help("Sort entries by fields, like year:desc,author")
+                    .long("sort-by")
+                    .takes_value(true)
+                    .value_name("FIELDS"),
+            )
+            .arg(
+                clap::Arg::new("field-order")
+                    .help("Write these fields first, like author,title,journal,year")
+                    .long("field-order")
+                    .takes_value(true)
+                    .value_name("FIELDS"),
+            )
+            .arg(
+                clap::Arg::new("indent")
+                    .help("Indent fields by N spaces, or by a tab with \"tab\"")
+                    .long("indent")
+                    .takes_value(true)
+                    .value_name("N"),
+            )
+            .arg(
+                clap::Arg::new("align")
+                    .help("Align the \"=\" of all fields of an entry")
+                    .long("align")
+                    .takes_value(false),
+            )
+            .arg(
+                clap::Arg::new("type-case")
+                    .help("Case of entry types, either lower, upper, or capitalized")
+                    .long("type-case")
+                    .takes_value(true)
+                    .value_name("CASE"),
+            )
+            .arg(
+                clap::Arg::new("quotes")
+                    .help("Delimit field values by quotes instead of braces")
+                    .long("quotes")
+                    .takes_value(false),
+            )
+            .arg(
+                clap::Arg::new("no-trailing-comma")
+                    .help("Don't write a comma after the last field of an entry")
+                    .long("no-trailing-comma")
+                    .takes_value(false),
+            )
+            .arg(
+                clap::Arg::new("string-field")
+                    .help("Replace frequent values of a field by @string macros, like journal")
+                    .long("string-field")
+                    .takes_value(true)
+                    .multiple_occurrences(true)
+                    .number_of_values(1)
+                    .value_name("FIELD"),
+            )
+            .arg(
+                clap::Arg::new("string-min-count")
+                    .help("Minimum number of occurrences of a value to get a macro (default 3)")
+                    .long("string-min-count")
+                    .takes_value(true)
+                    .value_name("N"),
+            )
+            .arg(
+                clap::Arg::new("strings")
+                    .help("File with @string macros to use, like \"jacm = Journal of the ACM\"")
+                    .long("strings")
+                    .takes_value(true)
+                    .value_name("FILE"),
+            )
+            .arg(
+                clap::Arg::new("month-macros")
+                    .help("Write months as macros like mar instead of {March}")
+                    .long("month-macros")
+                    .takes_value(false),
+            )
+            .arg(
+                clap::Arg::new("journal-list")
+                    .help("List of journals and abbreviations in JabRef format, like \"Full;Abbrev\"")
+                    .long("journal-list")
+                    .takes_value(true)
+                    .multiple_occurrences(true)
+                    .number_of_values(1)
+                    .value_name("FILE"),
+            )
+            .arg(
+                clap::Arg::new("journal-form")
+                    .help("Form of journal titles, either full, abbreviated, or both")
+                    .long("journal-form")
+                    .takes_value(true)
+                    .value_name("FORM"),
+            )
+            .arg(
+                clap::Arg::new("normalize-ids")
+                    .help("Normalize and validate DOIs, ISBNs, ISSNs, and URLs")
+                    .long("normalize-ids")
+                    .takes_value(false),
+            )
+            .arg(
+                clap::Arg::new("isbn13")
+                    .help("Convert ISBN-10 to ISBN-13 (implies --normalize-ids)")
+                    .long("isbn13")
+                    .takes_value(false),
+            )
+            .arg(
+                clap::Arg::new("normalize-pages")
+                    .help("Normalize page ranges like \"pp. 45 - 67\" to 45--67")
+                    .long("normalize-pages")
+                    .takes_value(false),
+            )
+            .arg(
+                clap::Arg::new("normalize-keywords")
+                    .help("Split keywords, remove duplicates, and join them with \", \"")
+                    .long("normalize-keywords")
+                    .takes_value(false),
+            )
+            .arg(
+                clap::Arg::new("keyword-separator")
+                    .help("Separator of keywords (implies --normalize-keywords), default ; , |")
+                    .long("keyword-separator")
+                    .takes_value(true)
+                    .multiple_occurrences(true)
+                    .number_of_values(1)
+                    .value_name("SEPARATOR"),
+            )
+            .arg(
+                clap::Arg::new("lowercase-keywords")
+                    .help("Convert keywords to lowercase (implies --normalize-keywords)")
+                    .long("lowercase-keywords")
+                    .takes_value(false),
+            )
             .get_matches();
 
-        // get defaults
+        // the subcommands only read the input files, global options like the delimiter are
+        // available in their matches
+        let subcommand = matches.subcommand();
+        let dry_run = matches.is_present("dry-run");
+        let globals = subcommand.map_or(&matches, |(_, x)| x);
+
+        // get defaults, the last file is the output file unless it is given by --output or
+        // nothing is written
+        let mut files: Vec<&str> = globals.values_of("files").unwrap().collect();
+        let file_output = match matches.value_of("output") {
+            Some(x) => x,
+            None if subcommand.is_some() || (dry_run && files.len() == 1) => "",
+            None => match files.len() {
+                1 => return Err(Error::Config(String::from("Missing output file"))),
+                _ => {
+                    let ret = files.pop().unwrap();
+                    // probably forgotten output file, e.g., of a shell-expanded "exports/*.csv"
+                    if std::path::Path::new(ret)
+                        .extension()
+                        .is_some_and(|x| x.eq_ignore_ascii_case("csv"))
+                    {
+                        return Err(Error::Config(format!(
+                            "Refusing to overwrite \"{}\" with the output, use --output to \
+                             give the output file",
+                            ret
+                        )));
+                    }
+                    ret
+                }
+            },
+        };
         let mut ret = Self {
             // input / output files
-            file_input: std::path::PathBuf::from(matches.value_of("input-file").unwrap()),
-            file_output: std::path::PathBuf::from(matches.value_of("output-file").unwrap()),
+            files_input: files.iter().map(std::path::PathBuf::from).collect(),
+            file_output: std::path::PathBuf::from(file_output),
+            file_rejects: matches
+                .value_of("rejects-file")
+                .map(std::path::PathBuf::from),
+            file_strings: matches.value_of("strings").map(std::path::PathBuf::from),
+            file_profile: matches.value_of("profile").map(std::path::PathBuf::from),
+            file_report: matches.value_of("report").map(std::path::PathBuf::from),
+
+            // row selection
+            filter: matches.value_of("where").map(String::from),
+
+            source_field: matches.value_of("source-field").map(String::from),
+            sort_by: matches.value_of("sort-by").map(String::from),
+            split_by: matches.value_of("split-by").map(String::from),
+
+            // grouping of rows
+            group_by: matches.value_of("group-by").map(String::from),
+            group_consecutive: matches.is_present("group-consecutive"),
 
             // Lazy switch (recover from errors)
-            csv_lazy: matches.is_present("lazy"),
+            csv_lazy: globals.is_present("lazy"),
 
             // prevent the use of defaults?
             mapping_defaults: !matches.is_present("no-defaults"),
 
+            // unicode <-> LaTeX conversion
+            latex_encode: matches.is_present("latex-encode"),
+            latex_decode: matches.is_present("latex-decode"),
+
+            // page ranges
+            normalize_pages: matches.is_present("normalize-pages"),
+
             // Set other fields to default values
             ..Default::default()
         };
@@ -157,9 +681,8 @@ impl Config {
         // handle field assignments
         if let Some(x) = matches.values_of("field-csv-to-bib") {
             for field in x {
-                let result: Vec<&str> = field.split('=').collect();
-                ret.csv_field_mapping
-                    .insert(String::from(result[0]), String::from(result[1]));
+                let (key, template) = parse_field_mapping(field)?;
+                ret.csv_field_mapping.insert(key, template);
             }
         }
 
@@ -169,19 +692,144 @@ impl Config {
             }
         }
 
+        // row selection
+        if let Some(x) = matches.value_of("skip") {
+            ret.skip_rows = x
+                .parse()
+                .map_err(|_| Error::Config(String::from("Invalid number of rows to skip given")))?;
+        }
+        if let Some(x) = matches.value_of("limit") {
+            ret.limit_rows = Some(
+                x.parse()
+                    .map_err(|_| Error::Config(String::from("Invalid row limit given")))?,
+            );
+        }
+        ret.dry_run = dry_run;
+        ret.inspect = matches!(subcommand, Some(("inspect", _)));
+        ret.print_profile = matches!(subcommand, Some(("suggest", _)));
+        ret.wizard = matches!(subcommand, Some(("wizard", _)));
+        ret.suggest_mapping = matches.is_present("suggest-mapping");
+
+        // aggregation rules of grouped rows
+        if let Some(x) = matches.values_of("aggregate") {
+            for rule in x {
+                let (field, aggregation) = rule
+                    .split_once('=')
+                    .and_then(|(k, v)| Some((k, crate::group::Aggregation::parse(v)?)))
+                    .ok_or_else(|| {
+                        Error::Config(format!("Invalid aggregation rule given: {}", rule))
+                    })?;
+                ret.aggregations.insert(String::from(field), aggregation);
+            }
+        }
+
+        // pass-through of unmapped columns
+        if let Some(x) = matches.values_of("passthrough-exclude") {
+            for column in x {
+                ret.passthrough_exclude.push(column.to_string());
+            }
+        }
+        ret.passthrough = matches.is_present("passthrough") || !ret.passthrough_exclude.is_empty();
+
+        // error tolerance
+        if let Some(x) = matches.value_of("max-errors") {
+            ret.max_errors = Some(
+                x.parse()
+                    .map_err(|_| Error::Config(String::from("Invalid number of errors given")))?,
+            );
+        }
+        if let Some(x) = matches.value_of("max-error-rate") {
+            ret.max_error_rate = Some(
+                crate::policy::parse_rate(x)
+                    .ok_or_else(|| Error::Config(String::from("Invalid error rate given")))?,
+            );
+        }
+
+        // title protection
+        if let Some(x) = matches.values_of("protect-word") {
+            for word in x {
+                ret.protected_words.push(word.to_string());
+            }
+        }
+        ret.protect_titles =
+            matches.is_present("protect-titles") || !ret.protected_words.is_empty();
+
+        // keywords
+        if let Some(x) = matches.values_of("keyword-separator") {
+            for separator in x {
+                ret.keyword_separators.push(separator.to_string());
+            }
+        }
+        ret.lowercase_keywords = matches.is_present("lowercase-keywords");
+        ret.normalize_keywords = matches.is_present("normalize-keywords")
+            || !ret.keyword_separators.is_empty()
+            || ret.lowercase_keywords;
+
+        // identifiers
+        ret.isbn13 = matches.is_present("isbn13");
+        ret.normalize_identifiers = matches.is_present("normalize-ids") || ret.isbn13;
+
+        // journal titles
+        if let Some(x) = matches.values_of("journal-list") {
+            for path in x {
+                ret.journal_lists.push(std::path::PathBuf::from(path));
+            }
+        }
+        if let Some(x) = matches.value_of("journal-form") {
+            ret.journal_form = match x.to_lowercase().as_str() {
+                "full" => crate::journals::JournalForm::Full,
+                "abbreviated" => crate::journals::JournalForm::Abbreviated,
+                "both" => crate::journals::JournalForm::Both,
+                _ => return Err(Error::Config(String::from("Unknown journal form given"))),
+            }
+        }
+
+        // report, JSON for files like "report.json"
+        ret.report_format = match matches.value_of("report-format") {
+            Some(x) => match x.to_lowercase().as_str() {
+                "text" => crate::report::ReportFormat::Text,
+                "json" => crate::report::ReportFormat::Json,
+                _ => return Err(Error::Config(String::from("Unknown report format given"))),
+            },
+            None => match ret.file_report.as_ref().and_then(|x| x.extension()) {
+                Some(x) if x.eq_ignore_ascii_case("json") => crate::report::ReportFormat::Json,
+                _ => crate::report::ReportFormat::Text,
+            },
+        };
+
+        // date options
+        if let Some(x) = matches.values_of("date-format") {
+            for format in x {
+                crate::date::check_format(format)?;
+                ret.date_formats.push(format.to_string());
+            }
+        }
+        ret.parse_dates = matches.is_present("parse-dates") || !ret.date_formats.is_empty();
+
+        if let Some(x) = matches.value_of("date-order") {
+            ret.date_order = match x.to_lowercase().as_str() {
+                "dmy" => crate::date::DateOrder::DayMonthYear,
+                "mdy" => crate::date::DateOrder::MonthDayYear,
+                _ => return Err(Error::Config(String::from("Unknown date order given"))),
+            }
+        };
+
         // csv options
-        if let Some(x) = matches.value_of("csv-delimiter") {
+        if let Some(x) = globals.value_of("csv-delimiter") {
             ret.csv_delimiter = String::from(x)
         };
 
-        // logging handling
-        if let Some(x) = matches.value_of("log-level") {
+        // logging handling, printed entries and columns are not mixed with progress messages
+        if ret.dry_run || subcommand.is_some() {
+            ret.log_level = log::LevelFilter::Warn;
+        }
+        if let Some(x) = globals.value_of("log-level") {
             ret.log_level = match x.to_lowercase().as_str() {
                 "debug" => log::LevelFilter::Debug,
                 "info" => log::LevelFilter::Info,
                 "warn" => log::LevelFilter::Warn,
                 "error" => log::LevelFilter::Error,
-                _ => return Err(anyhow!("Unknown log level given")),
+                _ => return Err(Error::Config(String::from("Unknown log level given"))),
             }
         };
 
@@ -190,6 +838,84 @@ impl Config {
             ret.output_type = OutputType::Bibtex;
         }
 
+        // layout of the output
+        if let Some(x) = matches.value_of("field-order") {
+            ret.format.field_order = x
+                .split(',')
+                .map(|x| x.trim().to_lowercase())
+                .filter(|x| !x.is_empty())
+                .collect();
+        }
+        if let Some(x) = matches.value_of("indent") {
+            ret.format.indent = match x {
+                "tab" => String::from("\t"),
+                x => " ".repeat(
+                    x.parse()
+                        .map_err(|_| Error::Config(String::from("Invalid indentation given")))?,
+                ),
+            };
+        }
+        if let Some(x) = matches.value_of("type-case") {
+            ret.format.type_case = match x.to_lowercase().as_str() {
+                "lower" => crate::bibwriter::TypeCase::Lower,
+                "upper" => crate::bibwriter::TypeCase::Upper,
+                "capitalized" => crate::bibwriter::TypeCase::Capitalized,
+                _ => return Err(Error::Config(String::from("Unknown type case given"))),
+            }
+        }
+        if matches.is_present("quotes") {
+            ret.format.delimiter = crate::bibwriter::Delimiter::Quotes;
+        }
+        ret.format.align = matches.is_present("align");
+        ret.format.trailing_comma = !matches.is_present("no-trailing-comma");
+
+        // string macros
+        if let Some(x) = matches.values_of("string-field") {
+            for field in x {
+                ret.string_fields.push(field.to_string());
+            }
+        }
+        if let Some(x) = matches.value_of("string-min-count") {
+            // with 0, every value would get a macro
+            ret.string_min_count = x.parse().ok().filter(|x| *x > 0).ok_or_else(|| {
+                Error::Config(String::from("Invalid number of occurrences given"))
+            })?;
+        }
+        ret.month_macros = matches.is_present("month-macros");
+
         Ok(ret)
     }
 }
+
+/// Split a field mapping like "title=[[TI]]" into the field and its template. Only the first "="
+/// separates them, conditions like "[[SO]] if [[DT]]==Proceedings Paper" may contain "==" or "!=".
+fn parse_field_mapping(mapping: &str) -> Result<(String, String), Error> {
+    let (field, template) = mapping
+        .split_once('=')
+        .ok_or_else(|| Error::Config(format!("Invalid field mapping given: {}", mapping)))?;
+    Ok((String::from(field), String::from(template)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_field_mapping() {
+        assert_eq!(
+            parse_field_mapping("booktitle=[[SO]] if [[DT]]==Proceedings Paper").unwrap(),
+            (
+                String::from("booktitle"),
+                String::from("[[SO]] if [[DT]]==Proceedings Paper")
+            )
+        );
+        assert_eq!(
+            parse_field_mapping("journal=[[SO]] if [[DT]]!=Proceedings Paper").unwrap(),
+            (
+                String::from("journal"),
+                String::from("[[SO]] if [[DT]]!=Proceedings Paper")
+            )
+        );
+        assert!(parse_field_mapping("title").is_err());
+    }
+}
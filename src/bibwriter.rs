@@ -1,64 +1,622 @@
-use anyhow::anyhow;
-use anyhow::Context;
+use log::warn;
+
+use crate::dialect;
+use crate::latex;
+use crate::macros::StringMacros;
+use crate::Error;
 
 /// BibWriter Trait
 pub trait BibWrite {
-    fn write(&mut self, entry: &biblatex::Entry) -> Result<(), anyhow::Error>;
+    /// Write an entry. Returns the entry as it was written, i.e., converted to the dialect of the
+    /// writer and with a unique key.
+    fn write(&mut self, entry: &biblatex::Entry) -> Result<biblatex::Entry, Error>;
     fn get_num_written_entries(&self) -> usize;
+    /// Use string macros in all following entries and write their definitions
+    fn set_macros(&mut self, _macros: &StringMacros) -> Result<(), Error> {
+        Ok(())
+    }
+    /// Use string macros in all following entries without writing their definitions, e.g., when
+    /// appending to a file that contains them already
+    fn use_macros(&mut self, _macros: &StringMacros) {}
+    /// Output files and their number of entries, if the writer writes to several files
+    fn get_written_files(&self) -> Vec<(std::path::PathBuf, usize)> {
+        Vec::new()
+    }
+    /// Keys that existed already and were renamed, as pairs of the original and the new key
+    fn get_renamed_keys(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+}
+
+/// Case of the entry types, e.g., "article", "ARTICLE", or "Article"
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TypeCase {
+    #[default]
+    Lower,
+    Upper,
+    Capitalized,
+}
+
+/// Delimiters of field values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Delimiter {
+    /// Curly braces, e.g., `title = {Some Title}`
+    #[default]
+    Braces,
+    /// Double quotes, e.g., `title = "Some Title"`
+    Quotes,
+}
+
+/// Layout of the written entries. The default layout is the same as the one of
+/// `biblatex::Entry::to_biblatex_string`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Format {
+    /// Fields that are written first in this order, all other fields follow alphabetically
+    pub field_order: Vec<String>,
+    /// Indentation of the fields, e.g., two spaces or a tab
+    pub indent: String,
+    /// Align the "=" of all fields of an entry
+    pub align: bool,
+    pub type_case: TypeCase,
+    pub delimiter: Delimiter,
+    /// Write a comma after the last field of an entry
+    pub trailing_comma: bool,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Self {
+            field_order: Vec::new(),
+            indent: String::new(),
+            align: false,
+            type_case: TypeCase::default(),
+            delimiter: Delimiter::default(),
+            trailing_comma: true,
+        }
+    }
 }
 
 /// Biblatex type implementing bibwriter trait
 pub struct BiblatexWriter<W: std::io::Write> {
     writer: W,
     counter: usize,
+    // Keys of all written entries
+    keys: std::collections::HashSet<String>,
+    // Original and new keys of renamed entries
+    renamed: Vec<(String, String)>,
+    // If set, non-ASCII characters are converted to LaTeX commands in all fields except these
+    // verbatim fields
+    latex_encoding: Option<Vec<String>>,
+    format: Format,
+    macros: StringMacros,
 }
 
 impl<W: std::io::Write> BiblatexWriter<W> {
     pub fn new(writer: W) -> Self {
-        Self { writer, counter: 0 }
+        Self {
+            writer,
+            counter: 0,
+            keys: std::collections::HashSet::new(),
+            renamed: Vec::new(),
+            latex_encoding: None,
+            format: Format::default(),
+            macros: StringMacros::default(),
+        }
+    }
+
+    pub fn with_latex_encoding(mut self, verbatim_fields: &[String]) -> Self {
+        self.latex_encoding = Some(verbatim_fields.to_vec());
+        self
+    }
+
+    pub fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
     }
 }
 
 impl<W: std::io::Write> BibWrite for BiblatexWriter<W> {
-    fn write(&mut self, entry: &biblatex::Entry) -> Result<(), anyhow::Error> {
-        write!(self.writer, "{}\n\n", entry.to_biblatex_string())
-            .context("Could not write entry to file")?;
+    fn write(&mut self, entry: &biblatex::Entry) -> Result<biblatex::Entry, Error> {
+        let (mut entry, unrepresentable) = dialect::to_biblatex(entry);
+        for field in unrepresentable {
+            warn!(
+                "Entry \"{}\": Field \"{}\" could not be converted to BibLaTeX.",
+                entry.key, field
+            );
+        }
+
+        let key = unique_key(&mut self.keys, &entry.key);
+        if key != entry.key {
+            self.renamed.push((entry.key.clone(), key.clone()));
+        }
+        entry.key = key;
+        entry.entry_type = entry.entry_type.to_biblatex();
+        write!(
+            self.writer,
+            "{}\n\n",
+            format_entry(
+                &entry,
+                &entry.entry_type,
+                self.latex_encoding.as_deref(),
+                &self.format,
+                &self.macros
+            )
+        )
+        .map_err(|e| Error::io(String::from("Could not write entry to file"), e))?;
         self.counter += 1;
 
-        Ok(())
+        Ok(entry)
     }
     fn get_num_written_entries(&self) -> usize {
         self.counter
     }
+    fn get_renamed_keys(&self) -> Vec<(String, String)> {
+        self.renamed.clone()
+    }
+    fn set_macros(&mut self, macros: &StringMacros) -> Result<(), Error> {
+        self.use_macros(macros);
+        write_definitions(
+            &mut self.writer,
+            macros,
+            self.latex_encoding.is_some(),
+            &self.format,
+        )
+    }
+    fn use_macros(&mut self, macros: &StringMacros) {
+        self.macros = macros.clone();
+    }
 }
 
 /// Bibtex type implementing bibwriter trait
 pub struct BibtexWriter<W: std::io::Write> {
     writer: W,
     counter: usize,
+    // Keys of all written entries
+    keys: std::collections::HashSet<String>,
+    // Original and new keys of renamed entries
+    renamed: Vec<(String, String)>,
+    // If set, non-ASCII characters are converted to LaTeX commands in all fields except these
+    // verbatim fields
+    latex_encoding: Option<Vec<String>>,
+    format: Format,
+    macros: StringMacros,
 }
 
 impl<W: std::io::Write> BibtexWriter<W> {
     pub fn new(writer: W) -> Self {
-        Self { writer, counter: 0 }
+        Self {
+            writer,
+            counter: 0,
+            keys: std::collections::HashSet::new(),
+            renamed: Vec::new(),
+            latex_encoding: None,
+            format: Format::default(),
+            macros: StringMacros::default(),
+        }
+    }
+
+    pub fn with_latex_encoding(mut self, verbatim_fields: &[String]) -> Self {
+        self.latex_encoding = Some(verbatim_fields.to_vec());
+        self
+    }
+
+    pub fn with_format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
     }
 }
 
 impl<W: std::io::Write> BibWrite for BibtexWriter<W> {
-    fn write(&mut self, entry: &biblatex::Entry) -> Result<(), anyhow::Error> {
+    fn write(&mut self, entry: &biblatex::Entry) -> Result<biblatex::Entry, Error> {
+        let (mut entry, unrepresentable) = dialect::to_bibtex(entry);
+        for field in unrepresentable {
+            warn!(
+                "Entry \"{}\": Field \"{}\" cannot be represented in BibTeX.",
+                entry.key, field
+            );
+        }
+        if let Some(date) = entry.get("date") {
+            return Err(Error::Bibtex {
+                line: 0,
+                key: entry.key.clone(),
+                message: format!(
+                    "Could not convert date \"{}\" to year and month",
+                    biblatex::ChunksExt::format_verbatim(date)
+                ),
+            });
+        }
+
+        let key = unique_key(&mut self.keys, &entry.key);
+        if key != entry.key {
+            self.renamed.push((entry.key.clone(), key.clone()));
+        }
+        entry.key = key;
+        entry.entry_type = entry.entry_type.to_bibtex();
         write!(
             self.writer,
             "{}\n\n",
-            entry
-                .to_bibtex_string()
-                .map_err(|e| anyhow!("TypeError: {}", e))?
+            format_entry(
+                &entry,
+                &entry.entry_type,
+                self.latex_encoding.as_deref(),
+                &self.format,
+                &self.macros
+            )
         )
-        .context("Could not write entry to file")?;
+        .map_err(|e| Error::io(String::from("Could not write entry to file"), e))?;
         self.counter += 1;
 
-        Ok(())
+        Ok(entry)
     }
     fn get_num_written_entries(&self) -> usize {
         self.counter
     }
+    fn get_renamed_keys(&self) -> Vec<(String, String)> {
+        self.renamed.clone()
+    }
+    fn set_macros(&mut self, macros: &StringMacros) -> Result<(), Error> {
+        self.use_macros(macros);
+        write_definitions(
+            &mut self.writer,
+            macros,
+            self.latex_encoding.is_some(),
+            &self.format,
+        )
+    }
+    fn use_macros(&mut self, macros: &StringMacros) {
+        self.macros = macros.clone();
+    }
+}
+
+/// Write `@string` definitions of macros, followed by an empty line
+fn write_definitions<W: std::io::Write>(
+    writer: &mut W,
+    macros: &StringMacros,
+    encode: bool,
+    format: &Format,
+) -> Result<(), Error> {
+    if macros.definitions().is_empty() {
+        return Ok(());
+    }
+    let mut ret = String::new();
+    for (name, value) in macros.definitions() {
+        let value = vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+            value.clone(),
+        ))];
+        ret.push_str(&format!(
+            "@{}{{{} = {}}}\n",
+            apply_type_case("string", format.type_case),
+            name,
+            format_chunks(&value, false, encode, format.delimiter)
+        ));
+    }
+    ret.push('\n');
+
+    write!(writer, "{}", ret)
+        .map_err(|e| Error::io(String::from("Could not write string macros to file"), e))
+}
+
+/// Make a key unique among the keys of all written entries by appending "_2", "_3", ... to
+/// duplicates
+pub(crate) fn unique_key(keys: &mut std::collections::HashSet<String>, key: &str) -> String {
+    let mut ret = String::from(key);
+    let mut counter = 1;
+    while keys.contains(&ret) {
+        counter += 1;
+        ret = format!("{}_{}", key, counter);
+    }
+    if ret != key {
+        warn!(
+            "Entry \"{}\": Key exists already, renamed to \"{}\".",
+            key, ret
+        );
+    }
+    keys.insert(ret.clone());
+
+    ret
+}
+
+/// Serialize an entry with the given entry type and layout. With the default layout, this is the
+/// same as `biblatex::Entry::to_biblatex_string`. If `latex_encoding` is set, non-ASCII
+/// characters are converted to LaTeX commands in all fields that are not contained in
+/// `latex_encoding`. Values that have a macro are replaced by its name.
+fn format_entry(
+    entry: &biblatex::Entry,
+    entry_type: &biblatex::EntryType,
+    latex_encoding: Option<&[String]>,
+    format: &Format,
+    macros: &StringMacros,
+) -> String {
+    let entry_type = apply_type_case(&entry_type.to_string(), format.type_case);
+    let mut ret = format!("@{}{{{},\n", entry_type, entry.key);
+
+    // fields of `field_order` first, then all other fields in alphabetical order
+    let mut fields: Vec<(&String, &biblatex::Chunks)> = format
+        .field_order
+        .iter()
+        .filter_map(|x| entry.fields.get_key_value(x))
+        .collect();
+    fields.extend(
+        entry
+            .fields
+            .iter()
+            .filter(|(key, _)| !format.field_order.contains(key)),
+    );
+    let width = match format.align {
+        true => fields.iter().map(|(key, _)| key.chars().count()).max(),
+        false => None,
+    }
+    .unwrap_or(0);
+
+    for (i, (key, value)) in fields.iter().enumerate() {
+        let encode = match latex_encoding {
+            Some(x) => !x.contains(key),
+            None => false,
+        };
+        ret.push_str(&format.indent);
+        ret.push_str(&format!("{:width$} = ", key, width = width));
+        match macros.lookup(key, value) {
+            Some(x) => ret.push_str(&x),
+            None => ret.push_str(&format_chunks(
+                value,
+                is_verbatim_field(key),
+                encode,
+                format.delimiter,
+            )),
+        }
+        if format.trailing_comma || i + 1 < fields.len() {
+            ret.push(',');
+        }
+        ret.push('\n');
+    }
+
+    ret.push('}');
+    ret
+}
+
+/// Change the case of an entry type
+fn apply_type_case(entry_type: &str, case: TypeCase) -> String {
+    match case {
+        TypeCase::Lower => String::from(entry_type),
+        TypeCase::Upper => entry_type.to_uppercase(),
+        TypeCase::Capitalized => {
+            let mut chars = entry_type.chars();
+            match chars.next() {
+                Some(c) => c.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        }
+    }
+}
+
+/// Serialize the chunks of a field, see `biblatex::ChunksExt::to_biblatex_string`. Quotes in
+/// values delimited by quotes are enclosed in braces.
+fn format_chunks(
+    chunks: biblatex::ChunksRef,
+    is_verbatim: bool,
+    encode: bool,
+    delimiter: Delimiter,
+) -> String {
+    let mut ret = String::new();
+    let mut extra_brace = false;
+
+    for chunk in chunks {
+        match &chunk.v {
+            biblatex::Chunk::Verbatim(_) if !extra_brace => {
+                ret.push('{');
+                extra_brace = true;
+            }
+            biblatex::Chunk::Normal(_) if extra_brace => {
+                ret.push('}');
+                extra_brace = false;
+            }
+            biblatex::Chunk::Math(_) => ret.push('$'),
+            _ => {}
+        }
+
+        let is_math = matches!(chunk.v, biblatex::Chunk::Math(_));
+        for c in chunk.v.get().chars() {
+            if is_escapable(c, is_verbatim) {
+                ret.push('\\');
+            }
+            match latex::encode_char(c) {
+                Some(x) if encode && !is_math => ret.push_str(&x),
+                _ if c == '"' && delimiter == Delimiter::Quotes && !extra_brace => {
+                    ret.push_str("{\"}")
+                }
+                _ => ret.push(c),
+            }
+        }
+
+        if is_math {
+            ret.push('$');
+        }
+    }
+
+    if extra_brace {
+        ret.push('}');
+    }
+    match delimiter {
+        Delimiter::Braces => format!("{{{}}}", ret),
+        Delimiter::Quotes => format!("\"{}\"", ret),
+    }
+}
+
+/// Fields that are escaped in verbatim mode by the `biblatex` crate
+fn is_verbatim_field(key: &str) -> bool {
+    matches!(
+        key,
+        "file" | "doi" | "uri" | "eprint" | "verba" | "verbb" | "verbc" | "pdf" | "url" | "urlraw"
+    )
+}
+
+/// Characters that have to be escaped, see `biblatex::resolve::is_escapable`
+fn is_escapable(c: char, is_verbatim: bool) -> bool {
+    match c {
+        '{' | '}' | '\\' => true,
+        '&' | '%' | '$' | '_' | ':' => !is_verbatim,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_entry_like_biblatex() {
+        let mut entry = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        entry.set(
+            "title",
+            vec![
+                biblatex::Spanned::detached(biblatex::Chunk::Normal(String::from("The "))),
+                biblatex::Spanned::detached(biblatex::Chunk::Verbatim(String::from("DNA"))),
+                biblatex::Spanned::detached(biblatex::Chunk::Normal(String::from(": 100% & $"))),
+            ],
+        );
+        entry.set(
+            "url",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Verbatim(
+                String::from("https://example.com/?a_b%20"),
+            ))],
+        );
+
+        assert_eq!(
+            format_entry(
+                &entry,
+                &entry.entry_type,
+                None,
+                &Format::default(),
+                &StringMacros::default()
+            ),
+            entry.to_biblatex_string()
+        );
+    }
+
+    #[test]
+    fn test_latex_encoding() {
+        let mut entry = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        entry.set(
+            "author",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from("Müller, Jörg"),
+            ))],
+        );
+        entry.set(
+            "file",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Verbatim(
+                String::from("Müller.pdf"),
+            ))],
+        );
+
+        let mut writer = BibtexWriter::new(Vec::new()).with_latex_encoding(&[String::from("file")]);
+        writer.write(&entry).unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.writer).unwrap(),
+            "@article{test1,\nauthor = {M{\\\"u}ller, J{\\\"o}rg},\nfile = {{Müller.pdf}},\n}\n\n"
+        );
+    }
+
+    #[test]
+    fn test_unique_keys() {
+        let entry = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Misc);
+
+        let mut writer = BiblatexWriter::new(Vec::new());
+        writer.write(&entry).unwrap();
+        assert_eq!(writer.write(&entry).unwrap().key, "test1_2");
+        writer.write(&entry).unwrap();
+
+        assert_eq!(writer.get_num_written_entries(), 3);
+        assert_eq!(
+            writer.get_renamed_keys(),
+            vec![
+                (String::from("test1"), String::from("test1_2")),
+                (String::from("test1"), String::from("test1_3")),
+            ]
+        );
+        assert_eq!(
+            String::from_utf8(writer.writer).unwrap(),
+            "@misc{test1,\n}\n\n@misc{test1_2,\n}\n\n@misc{test1_3,\n}\n\n"
+        );
+    }
+
+    #[test]
+    fn test_format() {
+        let mut entry = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        for (key, value) in [
+            ("year", "2021"),
+            ("title", "The \"Best\" Title"),
+            ("author", "Doe, Jane"),
+            ("journal", "Some Journal"),
+        ] {
+            entry.set(
+                key,
+                vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                    String::from(value),
+                ))],
+            );
+        }
+
+        let format = Format {
+            field_order: vec![String::from("author"), String::from("title")],
+            indent: String::from("  "),
+            align: true,
+            type_case: TypeCase::Capitalized,
+            delimiter: Delimiter::Quotes,
+            trailing_comma: false,
+        };
+        let mut writer = BibtexWriter::new(Vec::new()).with_format(format.clone());
+        writer.write(&entry).unwrap();
+        assert_eq!(
+            String::from_utf8(writer.writer).unwrap(),
+            "@Article{test1,\n  author  = \"Doe, Jane\",\n  title   = \"The {\"}Best{\"} Title\",\n  \
+             journal = \"Some Journal\",\n  year    = \"2021\"\n}\n\n"
+        );
+
+        let format = Format {
+            type_case: TypeCase::Upper,
+            ..Default::default()
+        };
+        assert_eq!(
+            format_entry(
+                &entry,
+                &entry.entry_type,
+                None,
+                &format,
+                &StringMacros::default()
+            ),
+            "@ARTICLE{test1,\nauthor = {Doe, Jane},\njournal = {Some Journal},\n\
+             title = {The \"Best\" Title},\nyear = {2021},\n}"
+        );
+    }
+
+    #[test]
+    fn test_macros() {
+        let mut entry = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        entry.set(
+            "journal",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from("Journal of the ACM"),
+            ))],
+        );
+        entry.set(
+            "month",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from("3"),
+            ))],
+        );
+
+        let mut macros = StringMacros::new(&[String::from("journal")]).with_months();
+        macros.add_frequent([&entry], 1);
+        let mut writer = BibtexWriter::new(Vec::new());
+        writer.set_macros(&macros).unwrap();
+        writer.write(&entry).unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.writer).unwrap(),
+            "@string{jacm = {Journal of the ACM}}\n\n\
+             @article{test1,\njournal = jacm,\nmonth = mar,\n}\n\n"
+        );
+    }
 }
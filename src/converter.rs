@@ -1,3 +1,71 @@
+use log::warn;
+
+/// Regex of the placeholders of CSV columns in templates, e.g., "[[Start Page]]"
+fn placeholder_regex() -> &'static regex::Regex {
+    static REGEX: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    REGEX.get_or_init(|| regex::Regex::new("\\[\\[(.+?)\\]\\]").unwrap())
+}
+
+/// Names of all CSV columns that are used in a template, e.g., "[[Start Page]]--[[End Page]]"
+pub fn template_columns(template: &str) -> Vec<String> {
+    placeholder_regex()
+        .captures_iter(template)
+        .map(|x| String::from(split_placeholder(&x[1]).0))
+        .collect()
+}
+
+/// Check that all regexes in a template, e.g., "[[Source~/Vol\. (\d+)/1]]", are valid
+pub fn check_template(template: &str) -> Result<(), crate::Error> {
+    compile_extractors(template, &mut std::collections::HashMap::new())
+}
+
+/// Compile all regexes in a template that are not in `extractors` yet and add them
+fn compile_extractors(
+    template: &str,
+    extractors: &mut std::collections::HashMap<String, regex::Regex>,
+) -> Result<(), crate::Error> {
+    for caps in placeholder_regex().captures_iter(template) {
+        if let (_, Some((x, _))) = split_placeholder(&caps[1]) {
+            if extractors.contains_key(x) {
+                continue;
+            }
+            let regex = regex::Regex::new(x).map_err(|e| {
+                crate::Error::Config(format!("Invalid regex in template \"{}\": {}", template, e))
+            })?;
+            extractors.insert(String::from(x), regex);
+        }
+    }
+
+    Ok(())
+}
+
+/// Split the content of a placeholder into the CSV column and an optional regex and capture
+/// group, e.g., "Source~/Vol\. (\d+)/1" into "Source" and ("Vol\. (\d+)", "1"). A placeholder
+/// ends at the first "]]", so the regex cannot contain "]]"; write "\]\]" instead.
+fn split_placeholder(placeholder: &str) -> (&str, Option<(&str, &str)>) {
+    match placeholder.split_once("~/") {
+        Some((column, rest)) => match rest.rsplit_once('/') {
+            Some(x) => (column, Some(x)),
+            None => (column, Some((rest, ""))),
+        },
+        None => (placeholder, None),
+    }
+}
+
+/// Field name for a CSV column: lowercase, with every run of other characters than letters and
+/// digits replaced by "_", e.g., "Start Page" becomes "start_page"
+pub fn field_name(column: &str) -> String {
+    let mut ret = String::new();
+    for c in column.trim().chars() {
+        if c.is_alphanumeric() {
+            ret.extend(c.to_lowercase());
+        } else if !ret.is_empty() && !ret.ends_with('_') {
+            ret.push('_');
+        }
+    }
+    ret.trim_end_matches('_').to_string()
+}
+
 /// Convert Fields According to Command Line Arguments
 pub struct FieldConverter<'a> {
     // Collection of bibtex entries and their corresponding CSV fields. CSV fields can also be
@@ -6,23 +74,144 @@ pub struct FieldConverter<'a> {
     // The regex to replace CSV fields with their corresponding entry. Saved here to compile the
     // regex only once.
     regex: regex::Regex,
+    // The regex to split a template into the template itself and a condition, e.g.,
+    // "[[SO]] if [[DT]]==Proceedings Paper".
+    condition: regex::Regex,
     // Fields that should stay as they are -- verbatim mode
     verbatim_fields: &'a mut Vec<String>,
+    // Parser for fields ending in "date". If `None`, dates are copied as they are.
+    date_parser: Option<crate::date::DateParser>,
+    // Decoder for LaTeX commands in CSV cells. If `None`, cells are copied as they are.
+    latex_decoder: Option<crate::latex::Decoder>,
+    // Protection of acronyms and proper nouns in titles. If `None`, titles are not changed.
+    title_protector: Option<crate::protect::TitleProtector>,
+    // Normalize page ranges, see `crate::pages::normalize`
+    normalize_pages: bool,
+    // Normalization of keyword lists. If `None`, keywords are copied as they are.
+    keyword_normalizer: Option<crate::keywords::KeywordNormalizer>,
+    // Normalization of DOIs, ISBNs, ISSNs, and URLs. If `None`, identifiers are not changed.
+    identifiers: Option<crate::identifiers::IdentifierNormalizer>,
+    // Journal list and the form of journal titles. If `None`, journal titles are not changed.
+    journals: Option<(crate::journals::JournalList, crate::journals::JournalForm)>,
+    // Columns that are excluded from pass-through. If `None`, only mapped fields are written.
+    passthrough: Option<Vec<String>>,
+    // CSV columns used by any template of `map`, these are not passed through
+    mapped_columns: std::collections::HashSet<String>,
+    // Compiled regexes of placeholders like "[[Source~/Vol\. (\d+)/1]]" by their source
+    extractors: std::collections::HashMap<String, regex::Regex>,
 }
 
 impl<'a> FieldConverter<'a> {
+    /// Create a new converter. Returns an error if a template contains an invalid regex.
     pub fn new(
         replacement_list: &'a mut std::collections::HashMap<String, String>,
         verbatim_fields: &'a mut Vec<String>,
-    ) -> Self {
-        Self {
+    ) -> Result<Self, crate::Error> {
+        let mapped_columns = replacement_list
+            .values()
+            .flat_map(|x| template_columns(x))
+            .collect();
+        let mut extractors = std::collections::HashMap::new();
+        for template in replacement_list.values() {
+            compile_extractors(template, &mut extractors)?;
+        }
+        Ok(Self {
             map: replacement_list,
-            regex: regex::Regex::new("\\[\\[(.+?)\\]\\]").unwrap(),
+            regex: placeholder_regex().clone(),
+            condition: regex::Regex::new("(?s)^(.*?) if \\[\\[(.+?)\\]\\]\\s*(==|!=)(.*)$")
+                .unwrap(),
             verbatim_fields,
-        }
+            date_parser: None,
+            latex_decoder: None,
+            title_protector: None,
+            normalize_pages: false,
+            keyword_normalizer: None,
+            identifiers: None,
+            journals: None,
+            passthrough: None,
+            mapped_columns,
+            extractors,
+        })
+    }
+
+    pub fn with_latex_decoding(mut self) -> Self {
+        self.latex_decoder = Some(crate::latex::Decoder::new());
+        self
+    }
+
+    pub fn with_title_protection(mut self, words: &[String]) -> Self {
+        self.title_protector = Some(crate::protect::TitleProtector::new(words));
+        self
+    }
+
+    /// Normalize page ranges like "pp. 45 - 67" to "45--67"
+    pub fn with_page_normalization(mut self) -> Self {
+        self.normalize_pages = true;
+        self
+    }
+
+    /// Split keyword lists, remove duplicates, and join them with ", "
+    pub fn with_keyword_normalization(
+        mut self,
+        normalizer: crate::keywords::KeywordNormalizer,
+    ) -> Self {
+        self.keyword_normalizer = Some(normalizer);
+        self
+    }
+
+    /// Normalize and validate DOIs, ISBNs, ISSNs, and URLs
+    pub fn with_identifier_normalization(
+        mut self,
+        normalizer: crate::identifiers::IdentifierNormalizer,
+    ) -> Self {
+        self.identifiers = Some(normalizer);
+        self
+    }
+
+    /// Replace journal titles by their full or abbreviated form in the list
+    pub fn with_journals(
+        mut self,
+        list: crate::journals::JournalList,
+        form: crate::journals::JournalForm,
+    ) -> Self {
+        self.journals = Some((list, form));
+        self
+    }
+
+    /// Copy every CSV column that is not used in the field mapping to a field of the same name.
+    /// Columns in `exclude` (by their CSV or field name) are left out.
+    pub fn with_passthrough(mut self, exclude: &[String]) -> Self {
+        self.passthrough = Some(exclude.to_vec());
+        self
+    }
+
+    pub fn verbatim_fields(&self) -> &[String] {
+        self.verbatim_fields
+    }
+
+    pub fn with_date_parser(mut self, parser: crate::date::DateParser) -> Self {
+        self.date_parser = Some(parser);
+        self
+    }
+
+    /// Map a field to a template, replacing its current template. Returns an error if the
+    /// template contains an invalid regex.
+    pub fn set_template(&mut self, field: &str, template: &str) -> Result<(), crate::Error> {
+        compile_extractors(template, &mut self.extractors)?;
+        self.map.insert(String::from(field), String::from(template));
+        self.update_mapped_columns();
+        Ok(())
+    }
+
+    fn update_mapped_columns(&mut self) {
+        self.mapped_columns = self
+            .map
+            .values()
+            .flat_map(|x| template_columns(x))
+            .collect();
     }
 
-    pub fn add_defaults(self) -> Self {
+    pub fn add_defaults(mut self) -> Self {
         // insert some defaults that may fit to the given column names in the csv file
         // insert only if key doesn't exist already
         self.map
@@ -50,6 +239,9 @@ impl<'a> FieldConverter<'a> {
             .entry(String::from("number"))
             .or_insert_with(|| String::from("[[issue]]"));
 
+        // the defaults contain no regexes that would have to be compiled
+        self.update_mapped_columns();
+
         // Insert into verbatim fields
         let tmp_verbfields = [
             "url", "file", "doi", "pdf", "eprint", "verba", "verbb", "verbc", "urlraw",
@@ -63,23 +255,157 @@ impl<'a> FieldConverter<'a> {
         self
     }
 
+    /// Value of a placeholder for the given row. For a placeholder like "Source~/Vol\. (\d+)/1",
+    /// the capture group (a number or a name) of the regex is extracted from the cell. Without a
+    /// capture group, the first group or, if the regex has none, the whole match is used. `None`
+    /// is returned if the column doesn't exist or the regex doesn't match.
+    fn lookup(
+        &self,
+        placeholder: &str,
+        input: &std::collections::HashMap<String, String>,
+    ) -> Option<String> {
+        let (column, extraction) = split_placeholder(placeholder);
+        let cell = input.get(column)?;
+        let (regex, group) = match extraction {
+            Some(x) => x,
+            None => return Some(cell.clone()),
+        };
+
+        let caps = self.extractors.get(regex)?.captures(cell)?;
+        let value = match group.parse::<usize>() {
+            Ok(x) => caps.get(x),
+            Err(_) if group.is_empty() => caps.get(1).or_else(|| caps.get(0)),
+            Err(_) => caps.name(group),
+        };
+
+        value.map(|x| String::from(x.as_str()))
+    }
+
+    /// Select the template that applies to the given row. A template can consist of several
+    /// alternatives separated by " else ", each with an optional condition like
+    /// "[[SO]] if [[DT]]==Proceedings Paper" or "[[SO]] if [[DT]]!=Article". The first
+    /// alternative whose condition holds is returned. If no condition holds, `None` is returned.
+    /// There is no escape, so a template cannot contain a literal " else ", and a literal " if "
+    /// must not be followed by a placeholder and "==" or "!=".
+    fn select_template<'t>(
+        &self,
+        template: &'t str,
+        input: &std::collections::HashMap<String, String>,
+    ) -> Option<&'t str> {
+        for alternative in template.split(" else ") {
+            let caps = match self.condition.captures(alternative) {
+                Some(x) => x,
+                None => return Some(alternative),
+            };
+            let cell = self.lookup(&caps[2], input).unwrap_or_default();
+            let equal = cell.trim() == caps[4].trim();
+            if equal == (&caps[3] == "==") {
+                return caps.get(1).map(|x| x.as_str());
+            }
+        }
+
+        None
+    }
+
+    fn is_verbatim_field(&self, field: &str) -> bool {
+        self.verbatim_fields.iter().any(|x| x == field)
+    }
+
+    /// Save a field in the entry. Depending on the field, LaTeX commands are decoded, dates are
+    /// parsed, page ranges and keywords are normalized, and titles are protected.
+    fn set_field(&self, entry: &mut biblatex::Entry, k: &str, result: std::borrow::Cow<str>) {
+        // decode LaTeX commands into unicode characters
+        let result = match &self.latex_decoder {
+            Some(decoder) if !self.is_verbatim_field(k) => {
+                std::borrow::Cow::Owned(decoder.decode(&result))
+            }
+            _ => result,
+        };
+
+        // bring dates into ISO 8601 format
+        let result = match &self.date_parser {
+            Some(parser) if k.ends_with("date") => match parser.parse(&result) {
+                Some(x) => std::borrow::Cow::Owned(x),
+                None => {
+                    warn!("Could not parse date \"{}\". Keeping it as it is.", result);
+                    result
+                }
+            },
+            _ => result,
+        };
+
+        // bring page ranges into the form "45--67"
+        let result = match self.normalize_pages && k == "pages" {
+            true => std::borrow::Cow::Owned(crate::pages::normalize(&result)),
+            false => result,
+        };
+
+        // split and join keywords
+        let result = match &self.keyword_normalizer {
+            Some(x) if crate::keywords::KeywordNormalizer::is_keyword_field(k) => {
+                std::borrow::Cow::Owned(x.normalize(&result))
+            }
+            _ => result,
+        };
+        if result.is_empty() {
+            return;
+        }
+
+        if self.is_verbatim_field(k) {
+            entry.set(
+                k,
+                vec![biblatex::Spanned::detached(biblatex::Chunk::Verbatim(
+                    result.into_owned(),
+                ))],
+            );
+        } else if let Some(protector) = self
+            .title_protector
+            .as_ref()
+            .filter(|_| crate::protect::TitleProtector::is_title_field(k))
+        {
+            entry.set(k, protector.protect(&result));
+        } else {
+            entry.set(
+                k,
+                vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                    result.into_owned(),
+                ))],
+            );
+        }
+    }
+
     pub fn convert_fields(
         &self,
         input: std::collections::HashMap<String, String>,
         default_key: String,
     ) -> biblatex::Entry {
+        let (entry, messages) = self.convert_fields_with_messages(input, default_key);
+        for message in messages {
+            warn!("{}", message);
+        }
+        entry
+    }
+
+    /// Convert a row like `convert_fields`, but return the messages on invalid identifiers
+    /// instead of logging them, e.g., to log them with the line of the row
+    pub fn convert_fields_with_messages(
+        &self,
+        input: std::collections::HashMap<String, String>,
+        default_key: String,
+    ) -> (biblatex::Entry, Vec<String>) {
         // Check whether we have a key specified in `self.map` and use this one or set a default
         // value.
         //
         // TODO that looks ugly!
-        let key: String = if let Some(x) = self.map.get("bibtexkey") {
+        let key: String = if let Some(x) = self
+            .map
+            .get("bibtexkey")
+            .and_then(|x| self.select_template(x, &input))
+        {
             self.regex
                 .replace_all(x, |caps: &regex::Captures| {
-                    if let Some(y) = input.get(&caps[1]) {
-                        y.to_string()
-                    } else {
-                        default_key.clone()
-                    }
+                    self.lookup(&caps[1], &input)
+                        .unwrap_or_else(|| default_key.clone())
                 })
                 .into_owned()
         } else {
@@ -90,14 +416,15 @@ impl<'a> FieldConverter<'a> {
         // default value.
         //
         // TODO that looks ugly!
-        let entrytype: String = if let Some(x) = self.map.get("entrytype") {
+        let entrytype: String = if let Some(x) = self
+            .map
+            .get("entrytype")
+            .and_then(|x| self.select_template(x, &input))
+        {
             self.regex
                 .replace_all(x, |caps: &regex::Captures| {
-                    if let Some(y) = input.get(&caps[1]) {
-                        y.to_string()
-                    } else {
-                        String::from("article")
-                    }
+                    self.lookup(&caps[1], &input)
+                        .unwrap_or_else(|| String::from("article"))
                 })
                 .into_owned()
         } else {
@@ -117,38 +444,68 @@ impl<'a> FieldConverter<'a> {
             if k == "bibtexkey" || k == "entrytype" {
                 continue;
             }
+            let v = match self.select_template(v, &input) {
+                Some(x) => x,
+                None => continue,
+            };
             // replace fields and save them in the `ret` entry. This is the output of the current
             // function and will be printed later
             let result = self.regex.replace_all(v, |caps: &regex::Captures| {
-                if let Some(x) = input.get(&caps[1]) {
-                    x
-                } else {
-                    ""
-                }
+                self.lookup(&caps[1], &input).unwrap_or_default()
             });
 
             if result.is_empty() {
                 continue;
             }
+            self.set_field(&mut ret, k, result);
+        }
 
-            if self.verbatim_fields.contains(k) {
-                ret.set(
-                    k,
-                    vec![biblatex::Spanned::detached(biblatex::Chunk::Verbatim(
-                        result.into_owned(),
-                    ))],
-                );
-            } else {
-                ret.set(
-                    k,
-                    vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
-                        result.into_owned(),
-                    ))],
-                );
+        // copy all other columns, sorted by name so that columns with the same field name (e.g.,
+        // "Funding Info" and "funding-info") always get the same field
+        if let Some(exclude) = &self.passthrough {
+            let mut columns: Vec<(&String, &String)> = input.iter().collect();
+            columns.sort();
+            let mut copied = std::collections::HashSet::new();
+            for (column, value) in columns {
+                let mut k = field_name(column);
+                if value.is_empty()
+                    || k.is_empty()
+                    || self.mapped_columns.contains(column)
+                    || exclude.iter().any(|x| x == column || *x == k)
+                    || self.map.contains_key(&k)
+                    || (ret.get(&k).is_some() && !copied.contains(&k))
+                {
+                    continue;
+                }
+                // another column has this field name already, append "_2", "_3", ...
+                if copied.contains(&k) {
+                    let mut counter = 2;
+                    while ret.get(&format!("{}_{}", k, counter)).is_some() {
+                        counter += 1;
+                    }
+                    warn!(
+                        "Columns with the same field name \"{}\", copying \"{}\" to \"{}_{}\".",
+                        k, column, k, counter
+                    );
+                    k = format!("{}_{}", k, counter);
+                }
+                self.set_field(&mut ret, &k, std::borrow::Cow::Borrowed(value));
+                copied.insert(k);
             }
         }
 
-        ret
+        // normalized identifiers, invalid ones are kept
+        let messages = match &self.identifiers {
+            Some(x) => x.normalize(&mut ret),
+            None => Vec::new(),
+        };
+
+        // full or abbreviated journal titles
+        if let Some((list, form)) = &self.journals {
+            list.apply(&mut ret, *form);
+        }
+
+        (ret, messages)
     }
 }
 
@@ -179,8 +536,9 @@ mod tests {
         let mut replacement_list = std::collections::HashMap::new();
         let mut verbatim_fields = std::vec::Vec::new();
 
-        let converter =
-            FieldConverter::new(&mut replacement_list, &mut verbatim_fields).add_defaults();
+        let converter = FieldConverter::new(&mut replacement_list, &mut verbatim_fields)
+            .unwrap()
+            .add_defaults();
         let ret = converter.convert_fields(input, String::from("test1"));
 
         assert_eq!(ret, output);
@@ -198,7 +556,7 @@ mod tests {
         replacement_list.insert(String::from("bibtexkey"), String::from("[[key]]"));
         let mut verbatim_fields = std::vec::Vec::new();
 
-        let converter = FieldConverter::new(&mut replacement_list, &mut verbatim_fields);
+        let converter = FieldConverter::new(&mut replacement_list, &mut verbatim_fields).unwrap();
         let ret = converter.convert_fields(input, String::from("test1"));
 
         assert_eq!(ret, output);
@@ -214,7 +572,7 @@ mod tests {
         replacement_list.insert(String::from("bibtexkey"), String::from("prefix_[[key]]"));
         let mut verbatim_fields = std::vec::Vec::new();
 
-        let converter = FieldConverter::new(&mut replacement_list, &mut verbatim_fields);
+        let converter = FieldConverter::new(&mut replacement_list, &mut verbatim_fields).unwrap();
         let ret = converter.convert_fields(input, String::from("test1"));
 
         assert_eq!(ret, output);
@@ -245,8 +603,9 @@ mod tests {
         replacement_list.insert(String::from("isbn"), String::from("[[ISBNs]]"));
         let mut verbatim_fields = std::vec::Vec::new();
 
-        let converter =
-            FieldConverter::new(&mut replacement_list, &mut verbatim_fields).add_defaults();
+        let converter = FieldConverter::new(&mut replacement_list, &mut verbatim_fields)
+            .unwrap()
+            .add_defaults();
         let ret = converter.convert_fields(input, String::from("test1"));
 
         assert_eq!(ret, output);
@@ -273,8 +632,9 @@ mod tests {
         );
         let mut verbatim_fields = std::vec::Vec::new();
 
-        let converter =
-            FieldConverter::new(&mut replacement_list, &mut verbatim_fields).add_defaults();
+        let converter = FieldConverter::new(&mut replacement_list, &mut verbatim_fields)
+            .unwrap()
+            .add_defaults();
         let ret = converter.convert_fields(input, String::from("test1"));
 
         assert_eq!(ret, output);
@@ -300,11 +660,374 @@ mod tests {
         );
         let mut verbatim_fields = std::vec::Vec::new();
 
-        let converter =
-            FieldConverter::new(&mut replacement_list, &mut verbatim_fields).add_defaults();
+        let converter = FieldConverter::new(&mut replacement_list, &mut verbatim_fields)
+            .unwrap()
+            .add_defaults();
+        let ret = converter.convert_fields(input, String::from("test1"));
+
+        assert_eq!(ret, output);
+    }
+
+    #[test]
+    fn test_conditional_fields() {
+        let mut replacement_list = std::collections::HashMap::new();
+        replacement_list.insert(
+            String::from("entrytype"),
+            String::from("inproceedings if [[DT]]==Proceedings Paper else article"),
+        );
+        replacement_list.insert(
+            String::from("journal"),
+            String::from("[[SO]] if [[DT]]!=Proceedings Paper"),
+        );
+        replacement_list.insert(
+            String::from("booktitle"),
+            String::from("[[SO]] if [[DT]]==Proceedings Paper"),
+        );
+        let mut verbatim_fields = std::vec::Vec::new();
+        let converter = FieldConverter::new(&mut replacement_list, &mut verbatim_fields).unwrap();
+
+        // an article
+        let mut input = std::collections::HashMap::new();
+        input.insert(String::from("DT"), String::from("Article"));
+        input.insert(String::from("SO"), String::from("Nature"));
+
+        let mut output = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        output.set(
+            "journal",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from("Nature"),
+            ))],
+        );
+        assert_eq!(
+            converter.convert_fields(input, String::from("test1")),
+            output
+        );
+
+        // a paper in conference proceedings
+        let mut input = std::collections::HashMap::new();
+        input.insert(String::from("DT"), String::from("Proceedings Paper"));
+        input.insert(String::from("SO"), String::from("Proc. of SIGGRAPH"));
+
+        let mut output =
+            biblatex::Entry::new(String::from("test2"), biblatex::EntryType::InProceedings);
+        output.set(
+            "booktitle",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from("Proc. of SIGGRAPH"),
+            ))],
+        );
+        assert_eq!(
+            converter.convert_fields(input, String::from("test2")),
+            output
+        );
+    }
+
+    #[test]
+    fn test_passthrough() {
+        let mut input = std::collections::HashMap::new();
+        input.insert(String::from("TI"), String::from("My eloquent title"));
+        input.insert(String::from("Start Page"), String::from("12"));
+        input.insert(String::from("Times Cited"), String::from("3"));
+        input.insert(String::from("Notes"), String::from(""));
+        input.insert(String::from("DOI"), String::from("10.1000/182"));
+
+        let mut output = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        output.set(
+            "title",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from("My eloquent title"),
+            ))],
+        );
+        output.set(
+            "start_page",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from("12"),
+            ))],
+        );
+        output.set(
+            "doi",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Verbatim(
+                String::from("10.1000/182"),
+            ))],
+        );
+
+        let mut replacement_list = std::collections::HashMap::new();
+        replacement_list.insert(String::from("title"), String::from("[[TI]]"));
+        let mut verbatim_fields = vec![String::from("doi")];
+
+        let converter = FieldConverter::new(&mut replacement_list, &mut verbatim_fields)
+            .unwrap()
+            .with_passthrough(&[String::from("Times Cited")]);
+        let ret = converter.convert_fields(input, String::from("test1"));
+
+        assert_eq!(ret, output);
+        assert_eq!(field_name(" Author Full-Names (1) "), "author_full_names_1");
+    }
+
+    #[test]
+    fn test_passthrough_same_field_name() {
+        let mut input = std::collections::HashMap::new();
+        input.insert(String::from("funding-info"), String::from("b"));
+        input.insert(String::from("Funding Info"), String::from("a"));
+        input.insert(String::from("FUNDING_INFO"), String::from("c"));
+
+        let mut replacement_list = std::collections::HashMap::new();
+        let mut verbatim_fields = Vec::new();
+        let converter = FieldConverter::new(&mut replacement_list, &mut verbatim_fields)
+            .unwrap()
+            .with_passthrough(&[]);
+
+        // columns are copied in the order of their names
+        let ret = converter.convert_fields(input, String::from("test1"));
+        let value = |k: &str| ret.get(k).map(biblatex::ChunksExt::format_verbatim);
+        assert_eq!(value("funding_info"), Some(String::from("c")));
+        assert_eq!(value("funding_info_2"), Some(String::from("a")));
+        assert_eq!(value("funding_info_3"), Some(String::from("b")));
+    }
+
+    #[test]
+    fn test_regex_extraction() {
+        let mut input = std::collections::HashMap::new();
+        input.insert(
+            String::from("Source"),
+            String::from("Vol. 12, No. 3, pp. 45-67"),
+        );
+        input.insert(
+            String::from("Link"),
+            String::from("https://doi.org/10.1000/182?via=csv"),
+        );
+
+        let mut output = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        output.set(
+            "volume",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from("12"),
+            ))],
+        );
+        output.set(
+            "number",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from("3"),
+            ))],
+        );
+        output.set(
+            "pages",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from("45--67"),
+            ))],
+        );
+        output.set(
+            "doi",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from("10.1000/182"),
+            ))],
+        );
+
+        let mut replacement_list = std::collections::HashMap::new();
+        replacement_list.insert(
+            String::from("volume"),
+            String::from("[[Source~/Vol\\. (\\d+)/1]]"),
+        );
+        replacement_list.insert(
+            String::from("number"),
+            String::from("[[Source~/No\\. (?P<no>\\d+)/no]]"),
+        );
+        replacement_list.insert(
+            String::from("pages"),
+            String::from("[[Source~/pp\\. (\\d+)-(\\d+)/1]]--[[Source~/pp\\. (\\d+)-(\\d+)/2]]"),
+        );
+        replacement_list.insert(String::from("doi"), String::from("[[Link~/10\\.[^?]+/]]"));
+        replacement_list.insert(
+            String::from("issn"),
+            String::from("[[Source~/ISSN (\\S+)/]]"),
+        );
+        let mut verbatim_fields = std::vec::Vec::new();
+
+        let converter = FieldConverter::new(&mut replacement_list, &mut verbatim_fields).unwrap();
         let ret = converter.convert_fields(input, String::from("test1"));
 
         assert_eq!(ret, output);
+        assert_eq!(
+            template_columns("[[Source~/Vol\\. (\\d+)/1]] [[Year]]"),
+            vec![String::from("Source"), String::from("Year")]
+        );
+        assert!(check_template("[[Source~/Vol\\. (\\d+/1]]").is_err());
+
+        // invalid regexes are found when the converter is created
+        let mut replacement_list = std::collections::HashMap::new();
+        replacement_list.insert(String::from("volume"), String::from("[[Source~/(\\d+/1]]"));
+        let mut verbatim_fields = std::vec::Vec::new();
+        assert!(FieldConverter::new(&mut replacement_list, &mut verbatim_fields).is_err());
+    }
+
+    #[test]
+    fn test_date_fields() {
+        let mut input = std::collections::HashMap::new();
+        input.insert(String::from("PD"), String::from("Mar 2021"));
+        input.insert(String::from("Accessed"), String::from("04/03/2021"));
+
+        let mut output = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        output.set(
+            "date",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from("2021-03"),
+            ))],
+        );
+        output.set(
+            "urldate",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from("2021-03-04"),
+            ))],
+        );
+
+        let mut replacement_list = std::collections::HashMap::new();
+        replacement_list.insert(String::from("date"), String::from("[[PD]]"));
+        replacement_list.insert(String::from("urldate"), String::from("[[Accessed]]"));
+        let mut verbatim_fields = std::vec::Vec::new();
+
+        let converter = FieldConverter::new(&mut replacement_list, &mut verbatim_fields)
+            .unwrap()
+            .with_date_parser(
+                crate::date::DateParser::new(&[], crate::date::DateOrder::DayMonthYear).unwrap(),
+            );
+        let ret = converter.convert_fields(input, String::from("test1"));
+
+        assert_eq!(ret, output);
+    }
+
+    #[test]
+    fn test_latex_decoding() {
+        let mut input = std::collections::HashMap::new();
+        input.insert(
+            String::from("author"),
+            String::from("M{\\\"u}ller, J\\\"org"),
+        );
+        input.insert(
+            String::from("url"),
+            String::from("https://example.com/\\\"u"),
+        );
+
+        let mut output = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        output.set(
+            "author",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from("Müller, Jörg"),
+            ))],
+        );
+        output.set(
+            "url",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Verbatim(
+                String::from("https://example.com/\\\"u"),
+            ))],
+        );
+
+        let mut replacement_list = std::collections::HashMap::new();
+        replacement_list.insert(String::from("url"), String::from("[[url]]"));
+        let mut verbatim_fields = std::vec::Vec::new();
+
+        let converter = FieldConverter::new(&mut replacement_list, &mut verbatim_fields)
+            .unwrap()
+            .add_defaults()
+            .with_latex_decoding();
+        let ret = converter.convert_fields(input, String::from("test1"));
+
+        assert_eq!(ret, output);
+    }
+
+    #[test]
+    fn test_title_protection() {
+        let mut input = std::collections::HashMap::new();
+        input.insert(
+            String::from("title"),
+            String::from("The DNA of Bayesian models"),
+        );
+        input.insert(String::from("abstract"), String::from("About DNA"));
+
+        let mut output = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        output.set(
+            "title",
+            vec![
+                biblatex::Spanned::detached(biblatex::Chunk::Normal(String::from("The "))),
+                biblatex::Spanned::detached(biblatex::Chunk::Verbatim(String::from("DNA"))),
+                biblatex::Spanned::detached(biblatex::Chunk::Normal(String::from(" of "))),
+                biblatex::Spanned::detached(biblatex::Chunk::Verbatim(String::from("Bayesian"))),
+                biblatex::Spanned::detached(biblatex::Chunk::Normal(String::from(" models"))),
+            ],
+        );
+        output.set(
+            "abstract",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from("About DNA"),
+            ))],
+        );
+
+        let mut replacement_list = std::collections::HashMap::new();
+        let mut verbatim_fields = std::vec::Vec::new();
+
+        let converter = FieldConverter::new(&mut replacement_list, &mut verbatim_fields)
+            .unwrap()
+            .add_defaults()
+            .with_title_protection(&[String::from("Bayesian")]);
+        let ret = converter.convert_fields(input, String::from("test1"));
+
+        assert_eq!(ret, output);
+        assert_eq!(
+            ret.to_biblatex_string(),
+            "@article{test1,\nabstract = {About DNA},\ntitle = {The {DNA} of {Bayesian} models},\n}"
+        );
+    }
+
+    #[test]
+    fn test_page_normalization() {
+        let mut replacement_list = std::collections::HashMap::new();
+        replacement_list.insert(String::from("pages"), String::from("[[BP]]--[[EP]]"));
+        let mut verbatim_fields = std::vec::Vec::new();
+        let converter = FieldConverter::new(&mut replacement_list, &mut verbatim_fields)
+            .unwrap()
+            .with_page_normalization();
+
+        let pages = |begin: &str, end: &str| {
+            let mut input = std::collections::HashMap::new();
+            input.insert(String::from("BP"), String::from(begin));
+            input.insert(String::from("EP"), String::from(end));
+            converter
+                .convert_fields(input, String::from("test1"))
+                .get("pages")
+                .map(biblatex::ChunksExt::format_verbatim)
+        };
+        assert_eq!(pages("1234", "45").as_deref(), Some("1234--1245"));
+        assert_eq!(pages("45", "").as_deref(), Some("45"));
+        assert_eq!(pages("e0123", "").as_deref(), Some("e0123"));
+        assert_eq!(pages("", ""), None);
+    }
+
+    #[test]
+    fn test_identifier_normalization() {
+        let mut replacement_list = std::collections::HashMap::new();
+        replacement_list.insert(String::from("doi"), String::from("[[DI]]"));
+        replacement_list.insert(String::from("issn"), String::from("[[SN]]"));
+        let mut verbatim_fields = vec![String::from("doi")];
+        let converter = FieldConverter::new(&mut replacement_list, &mut verbatim_fields)
+            .unwrap()
+            .with_identifier_normalization(crate::identifiers::IdentifierNormalizer::new());
+
+        let mut input = std::collections::HashMap::new();
+        input.insert(
+            String::from("DI"),
+            String::from("https://doi.org/10.1000/XYZ"),
+        );
+        input.insert(String::from("SN"), String::from("123456é"));
+        let (ret, messages) = converter.convert_fields_with_messages(input, String::from("test1"));
+        assert_eq!(
+            ret.get("doi").map(biblatex::ChunksExt::format_verbatim),
+            Some(String::from("10.1000/xyz"))
+        );
+        assert_eq!(
+            ret.get("issn").map(biblatex::ChunksExt::format_verbatim),
+            Some(String::from("123456é"))
+        );
+        // the invalid ISSN is reported to the caller, which knows the line of the row
+        assert_eq!(messages.len(), 1);
     }
 
     #[test]
@@ -342,8 +1065,9 @@ mod tests {
         replacement_list.insert(String::from("testfield"), String::from("[[testfield]]"));
         let mut verbatim_fields = vec![String::from("testfield")];
 
-        let converter =
-            FieldConverter::new(&mut replacement_list, &mut verbatim_fields).add_defaults();
+        let converter = FieldConverter::new(&mut replacement_list, &mut verbatim_fields)
+            .unwrap()
+            .add_defaults();
         let ret = converter.convert_fields(input, String::from("test1"));
 
         assert_eq!(ret, output);
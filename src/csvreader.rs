@@ -1,8 +1,62 @@
 use log::info;
 
+/// A single row of the CSV file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// Line of the row in the CSV file
+    pub line: u64,
+    /// Byte offset of the row in the CSV file
+    pub byte: u64,
+    /// Cells of the row as they appear in the CSV file
+    pub raw: Vec<String>,
+    /// Cells of the row by their column name
+    pub fields: std::collections::HashMap<String, String>,
+}
+
+/// A row of the CSV file that could not be read
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordError {
+    /// Line of the row in the CSV file
+    pub line: u64,
+    /// Byte offset of the row in the CSV file
+    pub byte: u64,
+    /// Cells of the row as far as they could be read
+    pub raw: Vec<String>,
+    /// Reason why the row could not be read
+    pub reason: String,
+}
+
+impl std::fmt::Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "Line {} (byte {}): {}",
+            self.line, self.byte, self.reason
+        )
+    }
+}
+
+impl std::error::Error for RecordError {}
+
 /// CSV Parser
 pub struct Reader<R> {
-    iterator: csv::DeserializeRecordsIntoIter<R, std::collections::HashMap<String, String>>,
+    reader: csv::Reader<R>,
+    headers: Vec<String>,
+    // Buffer for the current row
+    record: csv::ByteRecord,
+    // The csv crate reports positions before the "\n" of a "\r\n" line ending. In this case,
+    // line and byte have to be shifted by one.
+    position_offset: u64,
+    // Rows have to match this filter, other rows are skipped
+    filter: Option<crate::filter::Filter>,
+    // Number of rows to skip at the beginning of the file
+    skip: usize,
+    // Maximum number of rows to return
+    limit: Option<usize>,
+    // Number of rows that were skipped or didn't match the filter
+    filtered: usize,
+    // Number of rows returned so far
+    selected: usize,
 }
 
 impl<R: std::io::Read> Reader<R> {
@@ -16,21 +70,173 @@ impl<R: std::io::Read> Reader<R> {
             .flexible(error_recover)
             .from_reader(data);
 
-        info!("CSV file has {} columns.", reader.headers().unwrap().len());
+        let headers: Vec<String> = reader
+            .byte_headers()
+            .unwrap()
+            .iter()
+            .map(|x| String::from_utf8_lossy(x).into_owned())
+            .collect();
+
+        let position_offset = if reader.position().line() == 1 { 1 } else { 0 };
+
+        info!("CSV file has {} columns.", headers.len());
         // TODO pretty printing
-        info!("CSV columns: {:#?}.", reader.headers().unwrap());
+        info!("CSV columns: {:#?}.", headers);
 
         Self {
-            iterator: reader.into_deserialize(),
+            reader,
+            headers,
+            record: csv::ByteRecord::new(),
+            position_offset,
+            filter: None,
+            skip: 0,
+            limit: None,
+            filtered: 0,
+            selected: 0,
         }
     }
+
+    /// Return only rows that match the filter
+    pub fn with_filter(mut self, filter: crate::filter::Filter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Skip the first `skip` rows of the file
+    pub fn with_skip(mut self, skip: usize) -> Self {
+        self.skip = skip;
+        self
+    }
+
+    /// Return at most `limit` rows
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Number of rows that were skipped or didn't match the filter
+    pub fn get_num_filtered_rows(&self) -> usize {
+        self.filtered
+    }
+
+    /// Column names of the CSV file
+    pub fn headers(&self) -> &[String] {
+        &self.headers
+    }
 }
 
 impl<R: std::io::Read> Iterator for Reader<R> {
-    type Item = Result<std::collections::HashMap<String, String>, csv::Error>;
+    type Item = Result<Record, RecordError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.iterator.next()
+        if self.limit.is_some_and(|x| self.selected >= x) {
+            return None;
+        }
+
+        loop {
+            let ret = self.read_record()?;
+            if self.skip > 0 {
+                self.skip -= 1;
+                self.filtered += 1;
+                continue;
+            }
+            // rows that cannot be read are always returned, they are handled by the caller
+            if let (Ok(record), Some(filter)) = (&ret, &self.filter) {
+                if !filter.matches(&record.fields) {
+                    self.filtered += 1;
+                    continue;
+                }
+            }
+            self.selected += 1;
+            return Some(ret);
+        }
+    }
+}
+
+impl<R: std::io::Read> Reader<R> {
+    /// Read the next row of the CSV file
+    fn read_record(&mut self) -> Option<Result<Record, RecordError>> {
+        let result = self.reader.read_byte_record(&mut self.record);
+
+        let (line, byte) = match self.record.position() {
+            Some(x) => (
+                x.line() + self.position_offset,
+                x.byte() + self.position_offset,
+            ),
+            None => (0, 0),
+        };
+        let raw: Vec<String> = self
+            .record
+            .iter()
+            .map(|x| String::from_utf8_lossy(x).into_owned())
+            .collect();
+
+        match result {
+            Ok(false) => return None,
+            Ok(true) => {}
+            Err(e) => {
+                let reason = match e.kind() {
+                    csv::ErrorKind::UnequalLengths {
+                        expected_len, len, ..
+                    } => format!(
+                        "Found {} fields, but the previous record has {} fields",
+                        len, expected_len
+                    ),
+                    _ => e.to_string(),
+                };
+                return Some(Err(RecordError {
+                    line,
+                    byte,
+                    raw,
+                    reason,
+                }));
+            }
+        }
+
+        if let Err(e) = std::str::from_utf8(self.record.as_slice()) {
+            return Some(Err(RecordError {
+                line,
+                byte,
+                raw,
+                reason: format!("Invalid UTF-8: {}", e),
+            }));
+        }
+
+        // the csv crate accepts rows of any length in flexible mode, but only empty cells after
+        // the last column (e.g., from a trailing delimiter) can be dropped without losing data
+        let len = raw.len()
+            - raw
+                .iter()
+                .skip(self.headers.len())
+                .rev()
+                .take_while(|x| x.is_empty())
+                .count();
+        if len != self.headers.len() {
+            return Some(Err(RecordError {
+                line,
+                byte,
+                reason: format!(
+                    "Found {} fields, but the header has {} fields",
+                    raw.len(),
+                    self.headers.len()
+                ),
+                raw,
+            }));
+        }
+
+        let fields = self
+            .headers
+            .iter()
+            .cloned()
+            .zip(raw.iter().cloned())
+            .collect();
+
+        Some(Ok(Record {
+            line,
+            byte,
+            raw,
+            fields,
+        }))
     }
 }
 
@@ -51,6 +257,70 @@ mod tests {
         .cloned()
         .collect();
 
-        assert_eq!(parser.next().unwrap().unwrap(), result);
+        assert_eq!(parser.next().unwrap().unwrap().fields, result);
+    }
+
+    #[test]
+    fn test_csv_filter() {
+        let data = "author,year\nalice,2000\nbob,2010\ncarol,2020\ndave,2021\nerin,2022".as_bytes();
+        let mut parser = Reader::new(data, ",", false)
+            .with_filter(crate::filter::Filter::new("year>=2010").unwrap())
+            .with_skip(2)
+            .with_limit(2);
+
+        let authors: Vec<String> = parser
+            .by_ref()
+            .map(|x| x.unwrap().fields["author"].clone())
+            .collect();
+        assert_eq!(authors, vec![String::from("carol"), String::from("dave")]);
+        assert_eq!(parser.get_num_filtered_rows(), 2);
+    }
+
+    #[test]
+    fn test_csv_errors() {
+        let data = &b"author,year,title\nalice,2000\nbob,2001,\xff\ncarol,2002,title"[..];
+        let mut parser = Reader::new(data, ",", false);
+
+        let error = parser.next().unwrap().unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.byte, 18);
+        assert_eq!(error.raw, vec![String::from("alice"), String::from("2000")]);
+
+        let error = parser.next().unwrap().unwrap_err();
+        assert_eq!(error.line, 3);
+        assert!(error.reason.starts_with("Invalid UTF-8"));
+
+        let record = parser.next().unwrap().unwrap();
+        assert_eq!(record.line, 4);
+        assert_eq!(record.fields["author"], "carol");
+        assert!(parser.next().is_none());
+
+        // windows line endings
+        let data = "author,year\r\nalice,2000\r\nbob\r\n".as_bytes();
+        let mut parser = Reader::new(data, ",", false);
+
+        let record = parser.next().unwrap().unwrap();
+        assert_eq!((record.line, record.byte), (2, 13));
+        let error = parser.next().unwrap().unwrap_err();
+        assert_eq!((error.line, error.byte), (3, 25));
+
+        // rows that are too short or too long are errors in lazy mode, too
+        let data = "author,year\nalice\nbob,2001,extra\ncarol,2002,,\n".as_bytes();
+        let mut parser = Reader::new(data, ",", true);
+
+        let error = parser.next().unwrap().unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.reason, "Found 1 fields, but the header has 2 fields");
+        let error = parser.next().unwrap().unwrap_err();
+        assert_eq!(error.line, 3);
+        assert_eq!(
+            error.raw,
+            vec![
+                String::from("bob"),
+                String::from("2001"),
+                String::from("extra")
+            ]
+        );
+        assert_eq!(parser.next().unwrap().unwrap().fields["author"], "carol");
     }
 }
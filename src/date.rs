@@ -0,0 +1,278 @@
+/// Order of day and month in ambiguous numeric dates like `04/03/2021`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DateOrder {
+    #[default]
+    DayMonthYear,
+    MonthDayYear,
+}
+
+/// Seasons and their ISO 8601-2 month codes as supported by BibLaTeX
+const SEASONS: [(&str, u32); 5] = [
+    ("spring", 21),
+    ("summer", 22),
+    ("autumn", 23),
+    ("fall", 23),
+    ("winter", 24),
+];
+
+/// Parse dates in various formats and convert them to ISO 8601
+pub struct DateParser {
+    // Compiled formats, tried in the given order
+    formats: Vec<regex::Regex>,
+}
+
+impl DateParser {
+    /// Create a new parser. If `formats` is empty, a set of common formats is used. Formats use
+    /// the following placeholders: `%Y` (four digit year), `%m` (month as number), `%d` (day),
+    /// `%b` (month name or abbreviation), and `%S` (season like `Spring`). All other characters
+    /// are matched literally. Fails if a format contains an unknown or repeated placeholder.
+    pub fn new(formats: &[String], order: DateOrder) -> Result<Self, crate::Error> {
+        let formats: Vec<String> = if formats.is_empty() {
+            let numeric = match order {
+                DateOrder::DayMonthYear => ["%d/%m/%Y", "%d.%m.%Y"],
+                DateOrder::MonthDayYear => ["%m/%d/%Y", "%m.%d.%Y"],
+            };
+            [
+                "%Y-%m-%d",
+                "%Y-%m",
+                "%Y/%m/%d",
+                "%d %b %Y",
+                "%b %d, %Y",
+                "%b %d %Y",
+                "%b %Y",
+                "%Y %b",
+                "%S %Y",
+                "%Y %S",
+                "%Y",
+            ]
+            .iter()
+            .chain(numeric.iter())
+            .map(|x| String::from(*x))
+            .collect()
+        } else {
+            formats.to_vec()
+        };
+
+        Ok(Self {
+            formats: formats
+                .iter()
+                .map(|x| compile_format(x))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Parse a date and return it as ISO 8601 string (`YYYY-MM-DD`, `YYYY-MM`, or `YYYY`).
+    /// Seasons are returned with their ISO 8601-2 month code, e.g., `2021-21` for spring 2021.
+    pub fn parse(&self, input: &str) -> Option<String> {
+        let input = input.trim();
+
+        for format in &self.formats {
+            let caps = match format.captures(input) {
+                Some(x) => x,
+                None => continue,
+            };
+
+            // formats without a year are not supported
+            let year = match caps.name("y") {
+                Some(x) => x.as_str(),
+                None => continue,
+            };
+            let month = if let Some(x) = caps.name("m") {
+                x.as_str()
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|x| (1..=12).contains(x))
+            } else if let Some(x) = caps.name("b") {
+                month_from_name(x.as_str())
+            } else if let Some(x) = caps.name("s") {
+                season_from_name(x.as_str())
+            } else {
+                None
+            };
+            let day = caps.name("d").map(|x| {
+                x.as_str()
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|x| month.is_some_and(|m| (1..=days_in_month(year, m)).contains(x)))
+            });
+
+            return match (month, day) {
+                (Some(month), Some(Some(day))) => Some(format!("{}-{:02}-{:02}", year, month, day)),
+                (Some(month), None) => Some(format!("{}-{:02}", year, month)),
+                (None, None) if caps.name("m").is_none() && caps.name("b").is_none() => {
+                    if caps.name("s").is_some() {
+                        // unknown season
+                        continue;
+                    }
+                    Some(String::from(year))
+                }
+                _ => continue,
+            };
+        }
+
+        None
+    }
+}
+
+/// Check a user-supplied date format. Formats need a year and must not contain unknown or
+/// repeated placeholders.
+pub fn check_format(format: &str) -> Result<(), crate::Error> {
+    if !format.contains("%Y") {
+        return Err(crate::Error::Config(format!(
+            "Date format without year (%Y) given: {}",
+            format
+        )));
+    }
+    compile_format(format).map(|_| ())
+}
+
+/// Convert a format string into an anchored regex with named groups
+fn compile_format(format: &str) -> Result<regex::Regex, crate::Error> {
+    let mut pattern = String::from("^");
+    let mut placeholders = Vec::new();
+    let mut chars = format.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            pattern.push_str(&regex::escape(&c.to_string()));
+            continue;
+        }
+        let placeholder = chars.next();
+        if placeholders.contains(&placeholder) {
+            return Err(crate::Error::Config(format!(
+                "Date format with repeated placeholder given: {}",
+                format
+            )));
+        }
+        placeholders.push(placeholder);
+        match placeholder {
+            Some('Y') => pattern.push_str("(?P<y>\\d{4})"),
+            Some('m') => pattern.push_str("(?P<m>\\d{1,2})"),
+            Some('d') => pattern.push_str("(?P<d>\\d{1,2})"),
+            Some('b') => pattern.push_str("(?P<b>[[:alpha:]]+)\\.?"),
+            Some('S') => pattern.push_str("(?P<s>[[:alpha:]]+)"),
+            _ => {
+                return Err(crate::Error::Config(format!(
+                    "Date format with unknown placeholder given: {}",
+                    format
+                )))
+            }
+        }
+    }
+    pattern.push('$');
+
+    regex::RegexBuilder::new(&pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| crate::Error::Config(format!("Invalid date format \"{}\": {}", format, e)))
+}
+
+/// Number of days of a month, e.g., 29 for February of a leap year. Seasons have no days.
+fn days_in_month(year: &str, month: u32) -> u32 {
+    let year: u32 = year.parse().unwrap_or_default();
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => {
+            29
+        }
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => 0,
+    }
+}
+
+/// Get the month number (starting at 1) from an english month name or its abbreviation
+fn month_from_name(name: &str) -> Option<u32> {
+    let name = name.to_lowercase();
+    if name.len() < 3 {
+        return None;
+    }
+    [
+        "january",
+        "february",
+        "march",
+        "april",
+        "may",
+        "june",
+        "july",
+        "august",
+        "september",
+        "october",
+        "november",
+        "december",
+    ]
+    .iter()
+    .position(|x| x.starts_with(&name))
+    .map(|x| x as u32 + 1)
+}
+
+/// Get the ISO 8601-2 month code of a season
+fn season_from_name(name: &str) -> Option<u32> {
+    let name = name.to_lowercase();
+    SEASONS.iter().find(|x| x.0 == name).map(|x| x.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_formats() {
+        let parser = DateParser::new(&[], DateOrder::DayMonthYear).unwrap();
+
+        assert_eq!(parser.parse("2021-03-04"), Some(String::from("2021-03-04")));
+        assert_eq!(parser.parse("Mar 2021"), Some(String::from("2021-03")));
+        assert_eq!(
+            parser.parse("March 4, 2021"),
+            Some(String::from("2021-03-04"))
+        );
+        assert_eq!(parser.parse("04/03/2021"), Some(String::from("2021-03-04")));
+        assert_eq!(parser.parse("2021 Spring"), Some(String::from("2021-21")));
+        assert_eq!(parser.parse(" 2021 "), Some(String::from("2021")));
+        assert_eq!(parser.parse("sometime"), None);
+        assert_eq!(parser.parse("2021 Foo"), None);
+        assert_eq!(parser.parse("32/03/2021"), None);
+    }
+
+    #[test]
+    fn test_date_order() {
+        let parser = DateParser::new(&[], DateOrder::MonthDayYear).unwrap();
+
+        assert_eq!(parser.parse("04/03/2021"), Some(String::from("2021-04-03")));
+        assert_eq!(parser.parse("13/03/2021"), None);
+    }
+
+    #[test]
+    fn test_own_formats() {
+        let parser = DateParser::new(&[String::from("%Y%m%d")], DateOrder::DayMonthYear).unwrap();
+
+        assert_eq!(parser.parse("20210304"), Some(String::from("2021-03-04")));
+        assert_eq!(parser.parse("2021-03-04"), None);
+
+        // formats without a year never match
+        let parser = DateParser::new(&[String::from("%d.%m.")], DateOrder::DayMonthYear).unwrap();
+        assert_eq!(parser.parse("01.02."), None);
+    }
+
+    #[test]
+    fn test_bad_formats() {
+        assert!(check_format("%Y-%m-%d").is_ok());
+        assert!(check_format("%d.%m.").is_err());
+        assert!(check_format("%Y-%Y").is_err());
+        assert!(check_format("%Y-%x").is_err());
+        assert!(check_format("%Y%").is_err());
+        assert!(DateParser::new(&[String::from("%m/%Y/%m")], DateOrder::DayMonthYear).is_err());
+    }
+
+    #[test]
+    fn test_invalid_days() {
+        let parser = DateParser::new(&[], DateOrder::DayMonthYear).unwrap();
+
+        assert_eq!(parser.parse("31.02.2021"), None);
+        assert_eq!(parser.parse("2021-04-31"), None);
+        assert_eq!(parser.parse("29.02.2021"), None);
+        assert_eq!(parser.parse("29.02.2020"), Some(String::from("2020-02-29")));
+        assert_eq!(parser.parse("29.02.1900"), None);
+        assert_eq!(parser.parse("29.02.2000"), Some(String::from("2000-02-29")));
+    }
+}
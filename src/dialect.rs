@@ -0,0 +1,335 @@
+use biblatex::ChunksExt;
+
+/// Fields that only exist in BibLaTeX and have no counterpart in BibTeX
+const BIBLATEX_ONLY_FIELDS: [&str; 23] = [
+    "bookpagination",
+    "booksubtitle",
+    "booktitleaddon",
+    "eprintclass",
+    "eprinttype",
+    "eventdate",
+    "eventtitle",
+    "eventtitleaddon",
+    "issuesubtitle",
+    "issuetitle",
+    "journalsubtitle",
+    "langid",
+    "mainsubtitle",
+    "maintitle",
+    "maintitleaddon",
+    "origdate",
+    "pagetotal",
+    "pagination",
+    "shortjournal",
+    "subtitle",
+    "titleaddon",
+    "urldate",
+    "venue",
+];
+
+/// Field names that are renamed when converting from BibLaTeX to BibTeX
+const RENAMED_FIELDS: [(&str, &str); 2] = [("journaltitle", "journal"), ("location", "address")];
+
+/// English month names, their first three letters are the BibTeX month macros
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+/// Convert a BibLaTeX entry into an entry that can be represented in BibTeX.
+///
+/// Returns the converted entry and the names of all fields that could not be represented in
+/// BibTeX. These fields are kept in the entry, as BibTeX ignores unknown fields.
+pub fn to_bibtex(entry: &biblatex::Entry) -> (biblatex::Entry, Vec<String>) {
+    let mut ret = entry.clone();
+    let mut unrepresentable = Vec::new();
+
+    // date -> year / month
+    if let Some(date) = ret.remove("date") {
+        let date_string = date.format_verbatim();
+        match parse_date(&date) {
+            Some((year, month)) => {
+                set_if_missing(&mut ret, "year", year, &mut unrepresentable);
+                if let Some(month) = month {
+                    set_if_missing(&mut ret, "month", month, &mut unrepresentable);
+                }
+            }
+            None => {
+                // try to rescue at least the year
+                match find_year(&date_string) {
+                    Some(year) => set_if_missing(&mut ret, "year", year, &mut unrepresentable),
+                    None => ret.set("date", date),
+                }
+                unrepresentable.push(String::from("date"));
+            }
+        }
+    }
+
+    // renamed fields
+    for (from, to) in RENAMED_FIELDS {
+        rename(&mut ret, from, to, &mut unrepresentable);
+    }
+    if matches!(
+        ret.entry_type.to_bibtex(),
+        biblatex::EntryType::PhdThesis | biblatex::EntryType::MastersThesis
+    ) {
+        rename(&mut ret, "institution", "school", &mut unrepresentable);
+    }
+
+    // fields without any counterpart
+    for field in BIBLATEX_ONLY_FIELDS {
+        if ret.get(field).is_some() {
+            unrepresentable.push(String::from(field));
+        }
+    }
+
+    (ret, unrepresentable)
+}
+
+/// Convert a BibTeX entry into an entry using the BibLaTeX field names.
+///
+/// Returns the converted entry and the names of all fields that could not be converted because
+/// the target field is already set.
+pub fn to_biblatex(entry: &biblatex::Entry) -> (biblatex::Entry, Vec<String>) {
+    let mut ret = entry.clone();
+    let mut unrepresentable = Vec::new();
+
+    // year / month -> date
+    if ret.get("date").is_none() {
+        let year = ret.get("year").map(|x| x.format_verbatim());
+        let month = ret.get("month").map(|x| x.format_verbatim());
+        if let Some(date) = year.and_then(|y| build_date(&y, month.as_deref())) {
+            ret.remove("year");
+            ret.remove("month");
+            ret.set(
+                "date",
+                vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(date))],
+            );
+        }
+    }
+
+    // renamed fields
+    for (to, from) in RENAMED_FIELDS {
+        rename(&mut ret, from, to, &mut unrepresentable);
+    }
+    rename(&mut ret, "school", "institution", &mut unrepresentable);
+
+    (ret, unrepresentable)
+}
+
+/// Rename field `from` to `to` if `to` doesn't exist already. Otherwise, `from` is kept and
+/// reported as unrepresentable.
+fn rename(entry: &mut biblatex::Entry, from: &str, to: &str, unrepresentable: &mut Vec<String>) {
+    if entry.get(from).is_none() {
+        return;
+    }
+    if entry.get(to).is_some() {
+        unrepresentable.push(String::from(from));
+        return;
+    }
+    let value = entry.remove(from).unwrap();
+    entry.set(to, value);
+}
+
+/// Set a field to a normal string if it doesn't exist already, otherwise the field is
+/// unrepresentable
+fn set_if_missing(
+    entry: &mut biblatex::Entry,
+    field: &str,
+    value: String,
+    unrepresentable: &mut Vec<String>,
+) {
+    if entry.get(field).is_some() {
+        unrepresentable.push(String::from(field));
+        return;
+    }
+    entry.set(
+        field,
+        vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(value))],
+    );
+}
+
+/// Parse a BibLaTeX date into a BibTeX year and month
+fn parse_date(date: biblatex::ChunksRef) -> Option<(String, Option<String>)> {
+    // seasons (e.g., `2021-21` for spring 2021) have no BibTeX month
+    static SEASON: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let season = SEASON.get_or_init(|| regex::Regex::new("^\\s*(\\d{4})-2[1-4]\\s*$").unwrap());
+    if let Some(caps) = season.captures(&date.format_verbatim()) {
+        return Some((String::from(&caps[1]), None));
+    }
+
+    let date: biblatex::Date = date.parse().ok()?;
+    let (start, end) = match date.value {
+        biblatex::DateValue::At(x) | biblatex::DateValue::After(x) => (x, None),
+        biblatex::DateValue::Before(x) => (x, None),
+        biblatex::DateValue::Between(x, y) => (x, Some(y)),
+    };
+
+    let year = match end {
+        Some(end) if end.year != start.year => format!("{}--{}", start.year, end.year),
+        _ => format!("{}", start.year),
+    };
+    let month = match end {
+        Some(end) if end.year != start.year || end.month != start.month => None,
+        _ => start.month.map(|x| String::from(MONTHS[x as usize])),
+    };
+
+    Some((year, month))
+}
+
+/// Find the first four digit number in a string
+fn find_year(input: &str) -> Option<String> {
+    static REGEX: std::sync::OnceLock<regex::Regex> = std::sync::OnceLock::new();
+    let regex = REGEX.get_or_init(|| regex::Regex::new("\\b(\\d{4})\\b").unwrap());
+    regex.captures(input).map(|x| String::from(&x[1]))
+}
+
+/// Build an ISO 8601 date from BibTeX year and month fields. Returns `None` if the year is not a
+/// plain four digit number or the month cannot be recognized.
+fn build_date(year: &str, month: Option<&str>) -> Option<String> {
+    let year = year.trim();
+    if year.len() != 4 || !year.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    match month {
+        None => Some(String::from(year)),
+        Some(month) => Some(format!("{}-{:02}", year, month_number(month)?)),
+    }
+}
+
+/// Number of a month given as number (e.g., `3`), name (`March`), or abbreviation (`Mar`)
+fn month_number(month: &str) -> Option<usize> {
+    let month = month.trim().to_lowercase();
+    match month.parse::<usize>() {
+        Ok(x) if (1..=12).contains(&x) => Some(x),
+        Ok(_) => None,
+        Err(_) => MONTHS
+            .iter()
+            .position(|x| month.starts_with(&x[..3].to_lowercase()))
+            .map(|x| x + 1),
+    }
+}
+
+/// BibTeX month macro like `mar` of a month given as number, name, or abbreviation
+pub fn month_macro(month: &str) -> Option<String> {
+    month_number(month).map(|x| MONTHS[x - 1][..3].to_lowercase())
+}
+
+/// Name of a field in the other dialect, e.g., "journal" for "journaltitle" and vice versa
+pub fn counterpart(field: &str) -> Option<&'static str> {
+    RENAMED_FIELDS.iter().find_map(|(biblatex, bibtex)| {
+        if field == *biblatex {
+            Some(*bibtex)
+        } else if field == *bibtex {
+            Some(*biblatex)
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normal(value: &str) -> biblatex::Chunks {
+        vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+            String::from(value),
+        ))]
+    }
+
+    #[test]
+    fn test_to_bibtex() {
+        let mut input = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        input.set("date", normal("2021-03-04"));
+        input.set("journaltitle", normal("My Journal"));
+        input.set("location", normal("Berlin"));
+        input.set("urldate", normal("2022-01-01"));
+
+        let mut output = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        output.set("year", normal("2021"));
+        output.set("month", normal("March"));
+        output.set("journal", normal("My Journal"));
+        output.set("address", normal("Berlin"));
+        output.set("urldate", normal("2022-01-01"));
+
+        let (ret, unrepresentable) = to_bibtex(&input);
+        assert_eq!(ret, output);
+        assert_eq!(unrepresentable, vec![String::from("urldate")]);
+        assert!(ret.to_bibtex_string().is_ok());
+    }
+
+    #[test]
+    fn test_to_bibtex_malformed_date() {
+        let mut input = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        input.set("date", normal("Spring 2021"));
+
+        let mut output = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        output.set("year", normal("2021"));
+
+        let (ret, unrepresentable) = to_bibtex(&input);
+        assert_eq!(ret, output);
+        assert_eq!(unrepresentable, vec![String::from("date")]);
+
+        // seasons are reduced to the year
+        let mut input = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        input.set("date", normal("2021-21"));
+
+        let (ret, unrepresentable) = to_bibtex(&input);
+        assert_eq!(ret, output);
+        assert!(unrepresentable.is_empty());
+
+        // an existing month is kept, the month of the date is unrepresentable
+        let mut input = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        input.set("date", normal("2021-03"));
+        input.set("month", normal("April"));
+
+        let (ret, unrepresentable) = to_bibtex(&input);
+        assert_eq!(ret.get("month"), Some(normal("April").as_slice()));
+        assert_eq!(unrepresentable, vec![String::from("month")]);
+    }
+
+    #[test]
+    fn test_to_biblatex() {
+        let mut input = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        input.set("year", normal("2021"));
+        input.set("month", normal("Mar"));
+        input.set("journal", normal("My Journal"));
+        input.set("address", normal("Berlin"));
+
+        let mut output = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        output.set("date", normal("2021-03"));
+        output.set("journaltitle", normal("My Journal"));
+        output.set("location", normal("Berlin"));
+
+        let (ret, unrepresentable) = to_biblatex(&input);
+        assert_eq!(ret, output);
+        assert!(unrepresentable.is_empty());
+
+        // a year that isn't a plain number stays as it is
+        let mut input = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        input.set("year", normal("in press"));
+        let (ret, _) = to_biblatex(&input);
+        assert_eq!(ret, input);
+    }
+
+    #[test]
+    fn test_month_macro() {
+        assert_eq!(month_macro("3"), Some(String::from("mar")));
+        assert_eq!(month_macro("03"), Some(String::from("mar")));
+        assert_eq!(month_macro("September"), Some(String::from("sep")));
+        assert_eq!(month_macro("13"), None);
+        assert_eq!(month_macro("Spring"), None);
+    }
+}
@@ -0,0 +1,101 @@
+/// Errors that can occur while converting a CSV file
+#[derive(Debug)]
+pub enum Error {
+    /// Invalid command line arguments or configuration
+    Config(String),
+    /// A file could not be opened, read, or written
+    Io {
+        context: String,
+        source: std::io::Error,
+    },
+    /// A row of the CSV file could not be read
+    Csv {
+        line: u64,
+        byte: u64,
+        reason: String,
+    },
+    /// A field mapping refers to a column that doesn't exist in the CSV file
+    UnknownColumn { field: String, column: String },
+    /// An entry could not be represented in BibTeX
+    Bibtex {
+        line: u64,
+        key: String,
+        message: String,
+    },
+    /// More rows failed than tolerated
+    TooManyErrors { errors: usize, rows: usize },
+}
+
+impl Error {
+    /// Exit code of the process for this error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::Config(_) => 2,
+            Self::Io { .. } => 3,
+            Self::Csv { .. } => 4,
+            Self::UnknownColumn { .. } => 5,
+            Self::Bibtex { .. } => 6,
+            Self::TooManyErrors { .. } => 7,
+        }
+    }
+
+    /// Create an I/O error with some context, e.g., the file name
+    pub(crate) fn io(context: String, source: impl Into<std::io::Error>) -> Self {
+        Self::Io {
+            context,
+            source: source.into(),
+        }
+    }
+
+    /// Set the line of the CSV file the error belongs to
+    pub(crate) fn at_line(self, line: u64) -> Self {
+        match self {
+            Self::Bibtex { key, message, .. } => Self::Bibtex { line, key, message },
+            x => x,
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Config(x) => write!(f, "{}", x),
+            Self::Io { context, source } => write!(f, "{}: {}", context, source),
+            Self::Csv { line, byte, reason } => {
+                write!(f, "Line {} (byte {}): {}", line, byte, reason)
+            }
+            Self::UnknownColumn { field, column } => write!(
+                f,
+                "Field \"{}\" refers to unknown CSV column \"{}\"",
+                field, column
+            ),
+            Self::Bibtex { line, key, message } => {
+                write!(f, "Line {}: Entry \"{}\": {}", line, key, message)
+            }
+            Self::TooManyErrors { errors, rows } => write!(
+                f,
+                "Too many errors: {} of {} rows could not be converted",
+                errors, rows
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<crate::csvreader::RecordError> for Error {
+    fn from(e: crate::csvreader::RecordError) -> Self {
+        Self::Csv {
+            line: e.line,
+            byte: e.byte,
+            reason: e.reason,
+        }
+    }
+}
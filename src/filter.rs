@@ -0,0 +1,309 @@
+use crate::Error;
+
+/// Comparison operators of a filter expression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Matches,
+    NotMatches,
+}
+
+/// Tokens of a filter expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    // Column name or value, either bare, quoted, or enclosed in "[[" and "]]"
+    Value(String),
+    Operator(Operator),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+/// Parsed filter expression
+#[derive(Debug, Clone)]
+enum Expression {
+    Compare {
+        column: String,
+        operator: Operator,
+        value: String,
+        // Compiled regex for `=~` and `!~`
+        regex: Option<regex::Regex>,
+    },
+    Not(Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+}
+
+/// Filter for rows of the CSV file, e.g., "PY>=2015 && DT=='Article'". Comparisons are numeric
+/// if both sides are numbers and lexicographic otherwise. `=~` and `!~` match a column against a
+/// regex. Comparisons can be combined with `&&`, `||`, `!`, and parentheses.
+#[derive(Debug, Clone)]
+pub struct Filter {
+    expression: Expression,
+}
+
+impl Filter {
+    pub fn new(input: &str) -> Result<Self, Error> {
+        let parse = || {
+            let mut parser = Parser {
+                tokens: tokenize(input)?,
+                position: 0,
+            };
+            let expression = parser.parse_or()?;
+            match parser.tokens.get(parser.position) {
+                Some(x) => Err(format!("unexpected {:?}", x)),
+                None => Ok(expression),
+            }
+        };
+
+        parse()
+            .map(|expression| Self { expression })
+            .map_err(|e| Error::Config(format!("Invalid filter \"{}\": {}", input, e)))
+    }
+
+    /// Does the row match the filter?
+    pub fn matches(&self, fields: &std::collections::HashMap<String, String>) -> bool {
+        self.expression.eval(fields)
+    }
+
+    /// Names of all CSV columns that are used in the filter
+    pub fn columns(&self) -> Vec<String> {
+        let mut ret = Vec::new();
+        self.expression.columns(&mut ret);
+        ret
+    }
+}
+
+impl Expression {
+    fn eval(&self, fields: &std::collections::HashMap<String, String>) -> bool {
+        match self {
+            Self::Compare {
+                column,
+                operator,
+                value,
+                regex,
+            } => {
+                let cell = fields.get(column).map(|x| x.trim()).unwrap_or("");
+                if let Some(regex) = regex {
+                    return regex.is_match(cell) == (*operator == Operator::Matches);
+                }
+                let ordering = match (cell.parse::<f64>(), value.parse::<f64>()) {
+                    (Ok(x), Ok(y)) => x.partial_cmp(&y),
+                    _ => Some(cell.cmp(value.as_str())),
+                };
+                match ordering {
+                    Some(x) => match operator {
+                        Operator::Equal => x.is_eq(),
+                        Operator::NotEqual => x.is_ne(),
+                        Operator::Less => x.is_lt(),
+                        Operator::LessEqual => x.is_le(),
+                        Operator::Greater => x.is_gt(),
+                        Operator::GreaterEqual => x.is_ge(),
+                        Operator::Matches | Operator::NotMatches => false,
+                    },
+                    None => false,
+                }
+            }
+            Self::Not(x) => !x.eval(fields),
+            Self::And(x, y) => x.eval(fields) && y.eval(fields),
+            Self::Or(x, y) => x.eval(fields) || y.eval(fields),
+        }
+    }
+
+    fn columns(&self, ret: &mut Vec<String>) {
+        match self {
+            Self::Compare { column, .. } => {
+                if !ret.contains(column) {
+                    ret.push(column.clone());
+                }
+            }
+            Self::Not(x) => x.columns(ret),
+            Self::And(x, y) | Self::Or(x, y) => {
+                x.columns(ret);
+                y.columns(ret);
+            }
+        }
+    }
+}
+
+/// Split a filter expression into tokens
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut ret = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let (token, length) = match (chars[i], chars.get(i + 1).copied()) {
+            (x, _) if x.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('=', Some('=')) => (Token::Operator(Operator::Equal), 2),
+            ('=', Some('~')) => (Token::Operator(Operator::Matches), 2),
+            ('=', _) => (Token::Operator(Operator::Equal), 1),
+            ('!', Some('=')) => (Token::Operator(Operator::NotEqual), 2),
+            ('!', Some('~')) => (Token::Operator(Operator::NotMatches), 2),
+            ('!', _) => (Token::Not, 1),
+            ('<', Some('=')) => (Token::Operator(Operator::LessEqual), 2),
+            ('<', _) => (Token::Operator(Operator::Less), 1),
+            ('>', Some('=')) => (Token::Operator(Operator::GreaterEqual), 2),
+            ('>', _) => (Token::Operator(Operator::Greater), 1),
+            ('(', _) => (Token::Open, 1),
+            (')', _) => (Token::Close, 1),
+            (quote @ ('\'' | '"'), _) => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|x| *x == quote)
+                    .ok_or_else(|| String::from("missing closing quote"))?;
+                let value: String = chars[i + 1..i + 1 + end].iter().collect();
+                (Token::Value(value), end + 2)
+            }
+            ('[', Some('[')) => {
+                let rest: String = chars[i + 2..].iter().collect();
+                let end = rest
+                    .find("]]")
+                    .ok_or_else(|| String::from("missing closing \"]]\""))?;
+                let value = String::from(&rest[..end]);
+                (Token::Value(value.clone()), value.chars().count() + 4)
+            }
+            _ => {
+                let value: String = chars[i..]
+                    .iter()
+                    .take_while(|x| !x.is_whitespace() && !"&|=!<>()'\"".contains(**x))
+                    .collect();
+                if value.is_empty() {
+                    return Err(format!("unexpected character '{}'", chars[i]));
+                }
+                let length = value.chars().count();
+                (Token::Value(value), length)
+            }
+        };
+
+        ret.push(token);
+        i += length;
+    }
+
+    Ok(ret)
+}
+
+/// Recursive descent parser for filter expressions
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let ret = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        ret
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn parse_or(&mut self) -> Result<Expression, String> {
+        let mut ret = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            ret = Expression::Or(Box::new(ret), Box::new(self.parse_and()?));
+        }
+        Ok(ret)
+    }
+
+    fn parse_and(&mut self) -> Result<Expression, String> {
+        let mut ret = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.position += 1;
+            ret = Expression::And(Box::new(ret), Box::new(self.parse_unary()?));
+        }
+        Ok(ret)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expression, String> {
+        match self.next() {
+            Some(Token::Not) => Ok(Expression::Not(Box::new(self.parse_unary()?))),
+            Some(Token::Open) => {
+                let ret = self.parse_or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(ret),
+                    _ => Err(String::from("missing closing parenthesis")),
+                }
+            }
+            Some(Token::Value(column)) => {
+                let operator = match self.next() {
+                    Some(Token::Operator(x)) => x,
+                    _ => return Err(format!("missing operator after \"{}\"", column)),
+                };
+                let value = match self.next() {
+                    Some(Token::Value(x)) => x,
+                    _ => return Err(format!("missing value after \"{}\"", column)),
+                };
+                let regex = match operator {
+                    Operator::Matches | Operator::NotMatches => {
+                        Some(regex::Regex::new(&value).map_err(|e| e.to_string())?)
+                    }
+                    _ => None,
+                };
+                Ok(Expression::Compare {
+                    column,
+                    operator,
+                    value,
+                    regex,
+                })
+            }
+            Some(x) => Err(format!("unexpected {:?}", x)),
+            None => Err(String::from("unexpected end of filter")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter() {
+        let fields: std::collections::HashMap<String, String> = [
+            (String::from("PY"), String::from("2018")),
+            (String::from("DT"), String::from("Article")),
+            (String::from("Start Page"), String::from("12")),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+
+        let matches = |x: &str| Filter::new(x).unwrap().matches(&fields);
+        assert!(matches("PY>=2015 && DT=='Article'"));
+        assert!(!matches("PY<2015 || DT==\"Proceedings Paper\""));
+        assert!(matches("!(PY>2020) && [[Start Page]] < 100"));
+        assert!(matches("DT =~ '^Art' && DT !~ 'Paper'"));
+        assert!(matches("DT != Review"));
+        assert!(matches("AU == ''"));
+    }
+
+    #[test]
+    fn test_filter_errors() {
+        assert!(Filter::new("PY>=").is_err());
+        assert!(Filter::new("PY>=2015 &&").is_err());
+        assert!(Filter::new("(PY>=2015").is_err());
+        assert!(Filter::new("DT=='Article").is_err());
+        assert!(Filter::new("DT =~ '('").is_err());
+        assert_eq!(
+            Filter::new("PY>=2015 && ([[Start Page]]>1 || PY<2000)")
+                .unwrap()
+                .columns(),
+            vec![String::from("PY"), String::from("Start Page")]
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use crate::Error;
+
+/// Does the pattern contain wildcards?
+fn is_pattern(input: &str) -> bool {
+    input.contains(['*', '?', '['])
+}
+
+/// Expand a glob pattern like "exports/2021-*.csv" into the paths of all matching files, sorted
+/// by name. Wildcards are `*` (any characters), `?` (one character), and `[...]` (one character
+/// of a set like `[abc]`, `[0-9]`, or `[!0-9]`) and can be used in file and directory names.
+/// Paths without wildcards are returned as they are.
+pub fn expand(pattern: &str) -> Result<Vec<std::path::PathBuf>, Error> {
+    if !is_pattern(pattern) {
+        return Ok(vec![std::path::PathBuf::from(pattern)]);
+    }
+
+    let mut ret = vec![std::path::PathBuf::new()];
+    for component in std::path::Path::new(pattern).components() {
+        let component = component.as_os_str();
+        let name = component.to_string_lossy();
+        if !is_pattern(&name) {
+            ret.iter_mut().for_each(|x| x.push(component));
+            continue;
+        }
+
+        let mut matches = Vec::new();
+        for directory in &ret {
+            let dir = if directory.as_os_str().is_empty() {
+                std::path::Path::new(".")
+            } else {
+                directory.as_path()
+            };
+            let entries = match std::fs::read_dir(dir) {
+                Ok(x) => x,
+                Err(_) => continue,
+            };
+            for entry in entries.flatten() {
+                let file_name = entry.file_name();
+                let file_name = file_name.to_string_lossy();
+                // hidden files are only matched explicitly
+                if file_name.starts_with('.') && !name.starts_with('.') {
+                    continue;
+                }
+                if matches_pattern(&name, &file_name) {
+                    matches.push(directory.join(file_name.as_ref()));
+                }
+            }
+        }
+        ret = matches;
+    }
+
+    ret.retain(|x| x.is_file());
+    ret.sort();
+    if ret.is_empty() {
+        return Err(Error::Config(format!("No files match \"{}\"", pattern)));
+    }
+
+    Ok(ret)
+}
+
+/// Does the name match the glob pattern?
+fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    matches_chars(&pattern, &name)
+}
+
+fn matches_chars(pattern: &[char], name: &[char]) -> bool {
+    match pattern.first() {
+        None => name.is_empty(),
+        Some('*') => (0..=name.len()).any(|i| matches_chars(&pattern[1..], &name[i..])),
+        Some('?') => !name.is_empty() && matches_chars(&pattern[1..], &name[1..]),
+        Some('[') => {
+            let end = match pattern.iter().skip(2).position(|x| *x == ']') {
+                Some(x) => x + 2,
+                None => {
+                    return name.first() == Some(&'[') && matches_chars(&pattern[1..], &name[1..])
+                }
+            };
+            let c = match name.first() {
+                Some(x) => *x,
+                None => return false,
+            };
+            let (negate, set) = match pattern[1] {
+                '!' | '^' => (true, &pattern[2..end]),
+                _ => (false, &pattern[1..end]),
+            };
+            let mut found = false;
+            let mut i = 0;
+            while i < set.len() {
+                if i + 2 < set.len() && set[i + 1] == '-' {
+                    found |= set[i] <= c && c <= set[i + 2];
+                    i += 3;
+                } else {
+                    found |= set[i] == c;
+                    i += 1;
+                }
+            }
+            found != negate && matches_chars(&pattern[end + 1..], &name[1..])
+        }
+        Some(x) => name.first() == Some(x) && matches_chars(&pattern[1..], &name[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("*.csv", "export.csv"));
+        assert!(!matches_pattern("*.csv", "export.bib"));
+        assert!(matches_pattern("2021-??.csv", "2021-03.csv"));
+        assert!(!matches_pattern("2021-??.csv", "2021-3.csv"));
+        assert!(matches_pattern("export[0-9].csv", "export7.csv"));
+        assert!(!matches_pattern("export[!0-9].csv", "export7.csv"));
+        assert!(matches_pattern("a*b*c", "abbbc"));
+    }
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(
+            expand("./tests/test[12]-input*.csv").unwrap(),
+            vec![
+                std::path::PathBuf::from("./tests/test1-input1.csv"),
+                std::path::PathBuf::from("./tests/test2-input1.csv"),
+            ]
+        );
+        assert_eq!(
+            expand("no-pattern.csv").unwrap(),
+            vec![std::path::PathBuf::from("no-pattern.csv")]
+        );
+        assert!(expand("./tests/*.nothing").is_err());
+    }
+}
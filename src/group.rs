@@ -0,0 +1,253 @@
+/// Fields that contain a list of names
+const NAME_FIELDS: [&str; 6] = [
+    "author",
+    "editor",
+    "translator",
+    "bookauthor",
+    "commentator",
+    "annotator",
+];
+
+/// How the values of a field are combined when several rows are merged into one entry
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Aggregation {
+    /// Take the value of the first row that has one
+    First,
+    /// Take the value of the last row that has one
+    Last,
+    /// Join all distinct values with a separator
+    Join(String),
+}
+
+impl Aggregation {
+    /// Parse a rule, either "first", "last", or "join:SEPARATOR" like "join:; "
+    pub fn parse(input: &str) -> Option<Self> {
+        match input {
+            "first" => Some(Self::First),
+            "last" => Some(Self::Last),
+            x => x
+                .strip_prefix("join:")
+                .map(|separator| Self::Join(String::from(separator))),
+        }
+    }
+
+    /// Default rule of a field: names are joined with " and ", keywords with ", ", and for all
+    /// other fields, the first value is taken.
+    pub fn default_for(field: &str) -> Self {
+        if NAME_FIELDS.contains(&field) {
+            Self::Join(String::from(" and "))
+        } else if field == "keywords" {
+            Self::Join(String::from(", "))
+        } else {
+            Self::First
+        }
+    }
+}
+
+/// An entry converted from one or more rows of the CSV file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvertedRow {
+    /// Line of the (first) row in the CSV file
+    pub line: u64,
+    /// Byte offset of the (first) row in the CSV file
+    pub byte: u64,
+    /// Cells of the (first) row as they appear in the CSV file
+    pub raw: Vec<String>,
+    pub entry: biblatex::Entry,
+}
+
+/// Merge rows that share the value of a column into one entry
+pub struct Grouper {
+    // Column that identifies rows belonging together
+    column: String,
+    // Aggregation rules that differ from `Aggregation::default_for`
+    rules: std::collections::HashMap<String, Aggregation>,
+    // Merge only consecutive rows. Otherwise, all rows are kept until the end of the file.
+    consecutive: bool,
+    // Groups that are not finished yet together with their key
+    groups: Vec<(Option<String>, ConvertedRow)>,
+    // Position of a group in `groups` by its key
+    index: std::collections::HashMap<String, usize>,
+}
+
+impl Grouper {
+    pub fn new(
+        column: &str,
+        rules: &std::collections::HashMap<String, Aggregation>,
+        consecutive: bool,
+    ) -> Self {
+        Self {
+            column: String::from(column),
+            rules: rules.clone(),
+            consecutive,
+            groups: Vec::new(),
+            index: std::collections::HashMap::new(),
+        }
+    }
+
+    /// Key of the group the row belongs to. Rows without a key are never merged.
+    pub fn key(&self, fields: &std::collections::HashMap<String, String>) -> Option<String> {
+        fields
+            .get(&self.column)
+            .map(|x| x.trim())
+            .filter(|x| !x.is_empty())
+            .map(String::from)
+    }
+
+    /// Add a converted row to its group. Returns the groups that are finished, i.e., in
+    /// consecutive mode, the previous group if the key has changed.
+    pub fn add(&mut self, key: Option<String>, row: ConvertedRow) -> Vec<ConvertedRow> {
+        if let Some(i) = key.as_ref().and_then(|x| self.index.get(x)) {
+            let entry = &mut self.groups[*i].1.entry;
+            for (field, value) in row.entry.fields {
+                let rule = self
+                    .rules
+                    .get(&field)
+                    .cloned()
+                    .unwrap_or_else(|| Aggregation::default_for(&field));
+                merge_field(entry, &field, value, &rule);
+            }
+            return Vec::new();
+        }
+
+        let ret = if self.consecutive {
+            self.index.clear();
+            std::mem::take(&mut self.groups)
+                .into_iter()
+                .map(|x| x.1)
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        if let Some(x) = &key {
+            self.index.insert(x.clone(), self.groups.len());
+        }
+        self.groups.push((key, row));
+
+        ret
+    }
+
+    /// Return all remaining groups in the order of their first row
+    pub fn finish(self) -> Vec<ConvertedRow> {
+        self.groups.into_iter().map(|x| x.1).collect()
+    }
+}
+
+/// Merge a value into a field of an entry according to the aggregation rule
+fn merge_field(
+    entry: &mut biblatex::Entry,
+    field: &str,
+    value: biblatex::Chunks,
+    rule: &Aggregation,
+) {
+    let current = match entry.fields.get_mut(field) {
+        Some(x) => x,
+        None => {
+            entry.fields.insert(String::from(field), value);
+            return;
+        }
+    };
+
+    match rule {
+        Aggregation::First => {}
+        Aggregation::Last => *current = value,
+        Aggregation::Join(separator) => {
+            let separator = biblatex::Chunk::Normal(separator.clone());
+            let is_separator = |x: &biblatex::Spanned<biblatex::Chunk>| x.v == separator;
+            if current.split(is_separator).any(|x| x == value.as_slice()) {
+                return;
+            }
+            current.push(biblatex::Spanned::detached(separator.clone()));
+            current.extend(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(line: u64, fields: &[(&str, &str)]) -> ConvertedRow {
+        let mut entry =
+            biblatex::Entry::new(format!("entry_{}", line), biblatex::EntryType::Article);
+        for (k, v) in fields {
+            entry.set(
+                k,
+                vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                    String::from(*v),
+                ))],
+            );
+        }
+        ConvertedRow {
+            line,
+            byte: 0,
+            raw: Vec::new(),
+            entry,
+        }
+    }
+
+    #[test]
+    fn test_grouper() {
+        let mut rules = std::collections::HashMap::new();
+        rules.insert(String::from("note"), Aggregation::Last);
+        let mut grouper = Grouper::new("ID", &rules, false);
+
+        let key = |x: &str| Some(String::from(x));
+        assert!(grouper
+            .add(key("1"), row(2, &[("author", "Alice"), ("title", "First")]))
+            .is_empty());
+        assert!(grouper
+            .add(key("2"), row(3, &[("author", "Carol")]))
+            .is_empty());
+        assert!(grouper
+            .add(key("1"), row(4, &[("author", "Bob"), ("title", "Second")]))
+            .is_empty());
+        assert!(grouper
+            .add(key("1"), row(5, &[("author", "Alice"), ("note", "x")]))
+            .is_empty());
+        assert!(grouper.add(key("1"), row(6, &[("note", "y")])).is_empty());
+        assert!(grouper.add(None, row(7, &[("author", "Dave")])).is_empty());
+
+        let groups = grouper.finish();
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].line, 2);
+        assert_eq!(
+            groups[0].entry.to_biblatex_string(),
+            "@article{entry_2,\nauthor = {Alice and Bob},\nnote = {y},\ntitle = {First},\n}"
+        );
+        assert_eq!(groups[1].line, 3);
+        assert_eq!(groups[2].line, 7);
+    }
+
+    #[test]
+    fn test_grouper_consecutive() {
+        let mut grouper = Grouper::new("ID", &std::collections::HashMap::new(), true);
+        let key = |x: &str| Some(String::from(x));
+
+        assert!(grouper
+            .add(key("1"), row(2, &[("keywords", "a")]))
+            .is_empty());
+        assert!(grouper
+            .add(key("1"), row(3, &[("keywords", "b")]))
+            .is_empty());
+        let finished = grouper.add(key("2"), row(4, &[("keywords", "c")]));
+        assert_eq!(finished.len(), 1);
+        assert_eq!(
+            finished[0].entry.to_biblatex_string(),
+            "@article{entry_2,\nkeywords = {a, b},\n}"
+        );
+        assert_eq!(grouper.add(key("1"), row(5, &[])).len(), 1);
+        assert_eq!(grouper.finish().len(), 1);
+    }
+
+    #[test]
+    fn test_parse_aggregation() {
+        assert_eq!(Aggregation::parse("first"), Some(Aggregation::First));
+        assert_eq!(
+            Aggregation::parse("join:; "),
+            Some(Aggregation::Join(String::from("; ")))
+        );
+        assert_eq!(Aggregation::parse("sum"), None);
+    }
+}
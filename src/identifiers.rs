@@ -0,0 +1,371 @@
+/// Prefixes of DOIs that are removed, in lowercase
+const DOI_PREFIXES: [&str; 6] = [
+    "https://doi.org/",
+    "http://doi.org/",
+    "https://dx.doi.org/",
+    "http://dx.doi.org/",
+    "doi.org/",
+    "doi:",
+];
+
+/// Normalize and validate identifiers like DOIs, ISBNs, ISSNs, and URLs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IdentifierNormalizer {
+    // Convert ISBN-10 to ISBN-13
+    isbn13: bool,
+}
+
+impl IdentifierNormalizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Convert all ISBNs to ISBN-13
+    pub fn with_isbn13(mut self) -> Self {
+        self.isbn13 = true;
+        self
+    }
+
+    /// Normalize the fields `doi`, `isbn`, `issn`, and `url` of an entry. Invalid identifiers are
+    /// kept as they are. Returns a message for every invalid identifier.
+    pub fn normalize(&self, entry: &mut biblatex::Entry) -> Vec<String> {
+        let mut ret = Vec::new();
+
+        for (field, name) in [
+            ("doi", "DOI"),
+            ("isbn", "ISBN"),
+            ("issn", "ISSN"),
+            ("url", "URL"),
+        ] {
+            let (value, verbatim) = match entry.get(field) {
+                Some(x) => (
+                    biblatex::ChunksExt::format_verbatim(x),
+                    x.iter()
+                        .all(|x| matches!(x.v, biblatex::Chunk::Verbatim(_))),
+                ),
+                None => continue,
+            };
+
+            // ISBNs and ISSNs may be lists like "0-306-40615-2; 978-0-306-40615-7"
+            let separator = match field {
+                "isbn" | "issn" => value.chars().find(|c| *c == ';' || *c == ','),
+                _ => None,
+            };
+            let values: Vec<String> = match separator {
+                Some(c) => value.split(c).map(|x| String::from(x.trim())).collect(),
+                None => vec![String::from(value.trim())],
+            };
+
+            let mut normalized = Vec::new();
+            for x in values.iter().filter(|x| !x.is_empty()) {
+                let result = match field {
+                    "doi" => normalize_doi(x),
+                    "isbn" => normalize_isbn(x, self.isbn13),
+                    "issn" => normalize_issn(x),
+                    _ => normalize_url(x),
+                };
+                match result {
+                    Some(y) => normalized.push(y),
+                    None => {
+                        ret.push(format!(
+                            "Entry \"{}\": Invalid {} \"{}\".",
+                            entry.key, name, x
+                        ));
+                        normalized.push(x.clone());
+                    }
+                }
+            }
+
+            let result = match separator {
+                Some(c) => normalized.join(&format!("{} ", c)),
+                None => normalized.concat(),
+            };
+            let chunk = match verbatim {
+                true => biblatex::Chunk::Verbatim(result),
+                false => biblatex::Chunk::Normal(result),
+            };
+            entry.set(field, vec![biblatex::Spanned::detached(chunk)]);
+        }
+
+        ret
+    }
+}
+
+/// Remove prefixes like "https://doi.org/" or "doi:" and convert the DOI to lowercase. Returns
+/// `None` if the result is no DOI like "10.1000/xyz".
+pub fn normalize_doi(input: &str) -> Option<String> {
+    let mut ret = percent_decode(input.trim()).to_lowercase();
+    for prefix in DOI_PREFIXES {
+        if let Some(x) = ret.strip_prefix(prefix) {
+            ret = String::from(x.trim());
+            break;
+        }
+    }
+
+    let (prefix, suffix) = ret.split_once('/')?;
+    let registrant = prefix.strip_prefix("10.")?;
+    let is_valid = !registrant.is_empty()
+        && registrant.chars().all(|c| c.is_ascii_digit() || c == '.')
+        && !suffix.is_empty()
+        && !suffix.contains(char::is_whitespace);
+    is_valid.then_some(ret)
+}
+
+/// Remove hyphens, spaces, and a prefix like "ISBN-13:" and validate the checksum. ISBN-10 are
+/// converted to ISBN-13 if `to_isbn13` is set.
+pub fn normalize_isbn(input: &str, to_isbn13: bool) -> Option<String> {
+    let input = input.trim();
+    let input = match input.get(..4) {
+        Some(x) if x.eq_ignore_ascii_case("isbn") => input[4..]
+            .trim_start_matches(['-', '1', '3', '0'])
+            .trim_start_matches([':', ' ']),
+        _ => input,
+    };
+    let ret: String = input
+        .chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    // the length is checked in bytes, other characters than digits are invalid anyway
+    if !ret.is_ascii() {
+        return None;
+    }
+
+    match ret.len() {
+        10 if isbn10_check_digit(&ret[..9]) == ret.chars().last() => match to_isbn13 {
+            true => {
+                let digits = format!("978{}", &ret[..9]);
+                let check = isbn13_check_digit(&digits)?;
+                Some(format!("{}{}", digits, check))
+            }
+            false => Some(ret),
+        },
+        13 if isbn13_check_digit(&ret[..12]) == ret.chars().last() => Some(ret),
+        _ => None,
+    }
+}
+
+/// Check digit of the first nine digits of an ISBN-10
+fn isbn10_check_digit(digits: &str) -> Option<char> {
+    let mut sum = 0;
+    for (i, c) in digits.chars().enumerate() {
+        sum += (10 - i as u32) * c.to_digit(10)?;
+    }
+    match (11 - sum % 11) % 11 {
+        10 => Some('X'),
+        x => char::from_digit(x, 10),
+    }
+}
+
+/// Check digit of the first twelve digits of an ISBN-13
+fn isbn13_check_digit(digits: &str) -> Option<char> {
+    let mut sum = 0;
+    for (i, c) in digits.chars().enumerate() {
+        sum += if i % 2 == 0 { 1 } else { 3 } * c.to_digit(10)?;
+    }
+    char::from_digit((10 - sum % 10) % 10, 10)
+}
+
+/// Bring an ISSN into the form "1234-567X" and validate the checksum
+pub fn normalize_issn(input: &str) -> Option<String> {
+    let digits: String = input
+        .trim()
+        .trim_start_matches(|c: char| c.is_ascii_alphabetic() || c == ':' || c == ' ')
+        .chars()
+        .filter(|c| *c != '-' && !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    if digits.len() != 8 || !digits.is_ascii() {
+        return None;
+    }
+
+    let mut sum = 0;
+    for (i, c) in digits[..7].chars().enumerate() {
+        sum += (8 - i as u32) * c.to_digit(10)?;
+    }
+    let check = match (11 - sum % 11) % 11 {
+        10 => 'X',
+        x => char::from_digit(x, 10)?,
+    };
+    digits
+        .ends_with(check)
+        .then(|| format!("{}-{}", &digits[..4], &digits[4..]))
+}
+
+/// Lowercase the scheme and the host of a URL, percent-encode spaces and non-ASCII characters,
+/// and uppercase the hex digits of existing percent-encodings. Returns `None` if the URL has no
+/// scheme like "https://".
+pub fn normalize_url(input: &str) -> Option<String> {
+    let input = input.trim();
+    let (scheme, rest) = input.split_once("://")?;
+    let is_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+    if !is_scheme || rest.is_empty() {
+        return None;
+    }
+
+    let (host, path) = match rest.find(['/', '?', '#']) {
+        Some(i) => rest.split_at(i),
+        None => (rest, ""),
+    };
+    let mut ret = format!("{}://{}", scheme.to_lowercase(), host.to_lowercase());
+    let chars: Vec<char> = path.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '%'
+            && i + 2 < chars.len()
+            && chars[i + 1].is_ascii_hexdigit()
+            && chars[i + 2].is_ascii_hexdigit()
+        {
+            ret.push('%');
+            ret.push(chars[i + 1].to_ascii_uppercase());
+            ret.push(chars[i + 2].to_ascii_uppercase());
+            i += 3;
+            continue;
+        }
+        if c.is_ascii_graphic() && !"\"<>\\^`{|}".contains(c) {
+            ret.push(c);
+        } else {
+            let mut buffer = [0; 4];
+            for byte in c.encode_utf8(&mut buffer).bytes() {
+                ret.push_str(&format!("%{:02X}", byte));
+            }
+        }
+        i += 1;
+    }
+
+    Some(ret)
+}
+
+/// Decode percent-encoded characters like "%2F"
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut ret = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|x| std::str::from_utf8(x).ok())
+            .filter(|_| bytes[i] == b'%')
+            .and_then(|x| u8::from_str_radix(x, 16).ok());
+        match hex {
+            Some(x) => {
+                ret.push(x);
+                i += 3;
+            }
+            None => {
+                ret.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(ret).unwrap_or_else(|_| String::from(input))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_doi() {
+        assert_eq!(
+            normalize_doi("https://doi.org/10.1000/XYZ"),
+            Some(String::from("10.1000/xyz"))
+        );
+        assert_eq!(
+            normalize_doi("doi:10.1000/xyz "),
+            Some(String::from("10.1000/xyz"))
+        );
+        assert_eq!(
+            normalize_doi("http://dx.doi.org/10.1109%2FEMBC.2014.6944050"),
+            Some(String::from("10.1109/embc.2014.6944050"))
+        );
+        assert_eq!(normalize_doi("11.1000/xyz"), None);
+        assert_eq!(normalize_doi("10.1000"), None);
+    }
+
+    #[test]
+    fn test_normalize_isbn() {
+        assert_eq!(
+            normalize_isbn("978-1-4244-7929-0", false),
+            Some(String::from("9781424479290"))
+        );
+        assert_eq!(
+            normalize_isbn("ISBN-10: 0-306-40615-2", false),
+            Some(String::from("0306406152"))
+        );
+        assert_eq!(
+            normalize_isbn("0-306-40615-2", true),
+            Some(String::from("9780306406157"))
+        );
+        assert_eq!(
+            normalize_isbn("0-8044-2957-x", false),
+            Some(String::from("080442957X"))
+        );
+        assert_eq!(normalize_isbn("978-1-4244-7929-1", false), None);
+        assert_eq!(normalize_isbn("0-306-40615-3", true), None);
+        assert_eq!(normalize_isbn("12345678é", false), None);
+        assert_eq!(normalize_isbn("97814244792é", false), None);
+    }
+
+    #[test]
+    fn test_normalize_issn() {
+        assert_eq!(normalize_issn("1558-4615"), Some(String::from("1558-4615")));
+        assert_eq!(
+            normalize_issn("ISSN 1050124x"),
+            Some(String::from("1050-124X"))
+        );
+        assert_eq!(normalize_issn("1558-4616"), None);
+        assert_eq!(normalize_issn("1558-461"), None);
+        assert_eq!(normalize_issn("123456é"), None);
+    }
+
+    #[test]
+    fn test_normalize_url() {
+        assert_eq!(
+            normalize_url(" HTTPS://Example.COM/a b/ü?q=%2f "),
+            Some(String::from("https://example.com/a%20b/%C3%BC?q=%2F"))
+        );
+        assert_eq!(normalize_url("www.example.com"), None);
+    }
+
+    #[test]
+    fn test_normalize_entry() {
+        let mut entry = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Book);
+        entry.set(
+            "doi",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Verbatim(
+                String::from("doi:10.1000/XYZ"),
+            ))],
+        );
+        entry.set(
+            "isbn",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from("0-306-40615-2; 978-1-4244-7929-1"),
+            ))],
+        );
+
+        let messages = IdentifierNormalizer::new()
+            .with_isbn13()
+            .normalize(&mut entry);
+        assert_eq!(
+            messages,
+            vec![String::from(
+                "Entry \"test1\": Invalid ISBN \"978-1-4244-7929-1\"."
+            )]
+        );
+        assert_eq!(
+            entry.get("doi").unwrap(),
+            &[biblatex::Spanned::detached(biblatex::Chunk::Verbatim(
+                String::from("10.1000/xyz")
+            ))]
+        );
+        assert_eq!(
+            biblatex::ChunksExt::format_verbatim(entry.get("isbn").unwrap()),
+            "9780306406157; 978-1-4244-7929-1"
+        );
+    }
+}
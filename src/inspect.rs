@@ -0,0 +1,226 @@
+use log::warn;
+
+use crate::args;
+use crate::csvreader;
+use crate::glob;
+use crate::suggest;
+use crate::Error;
+
+/// Number of distinct sample values shown per column
+const SAMPLES: usize = 3;
+
+/// Maximum length of a sample value, longer values are truncated
+const SAMPLE_LENGTH: usize = 40;
+
+/// Fill rate and sample values of a CSV column
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    /// Name of the column in the header
+    pub name: String,
+    /// Number of rows with a non-empty cell
+    pub filled: usize,
+    /// Distinct non-empty values of the first rows
+    pub samples: Vec<String>,
+}
+
+/// Overview of the columns of a CSV file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inspection {
+    /// Number of rows that could be read
+    pub rows: usize,
+    /// Columns in the order of the header
+    pub columns: Vec<Column>,
+}
+
+impl Inspection {
+    /// Read all rows of a CSV file. Rows that cannot be read are skipped with a warning.
+    pub fn new<R: std::io::Read>(reader: csvreader::Reader<R>) -> Self {
+        let mut columns: Vec<Column> = reader
+            .headers()
+            .iter()
+            .map(|x| Column {
+                name: x.clone(),
+                filled: 0,
+                samples: Vec::new(),
+            })
+            .collect();
+        let mut rows = 0;
+
+        for record in reader {
+            let record = match record {
+                Ok(x) => x,
+                Err(e) => {
+                    warn!("{}.", e);
+                    continue;
+                }
+            };
+            rows += 1;
+            for (column, value) in columns.iter_mut().zip(record.raw.iter()) {
+                let value = value.trim();
+                if value.is_empty() {
+                    continue;
+                }
+                column.filled += 1;
+                if column.samples.len() < SAMPLES && !column.samples.iter().any(|x| x == value) {
+                    column.samples.push(String::from(value));
+                }
+            }
+        }
+
+        Self { rows, columns }
+    }
+
+    /// Guess the mapping from the column names, see `suggest::suggest`
+    pub fn guess_mapping(&self) -> Vec<suggest::Suggestion> {
+        let headers: Vec<String> = self.columns.iter().map(|x| x.name.clone()).collect();
+        suggest::suggest(&headers, &std::collections::HashMap::new())
+    }
+
+    /// Print a table of the columns and the guessed mapping
+    pub fn write<W: std::io::Write>(&self, out: &mut W, name: &str) -> std::io::Result<()> {
+        writeln!(
+            out,
+            "File \"{}\": {} rows, {} columns",
+            name,
+            self.rows,
+            self.columns.len()
+        )?;
+        writeln!(out)?;
+
+        let width = self
+            .columns
+            .iter()
+            .map(|x| x.name.chars().count())
+            .chain(std::iter::once("Column".len()))
+            .max()
+            .unwrap_or_default();
+        let digits = self.columns.len().to_string().len();
+        writeln!(
+            out,
+            "{:>digits$}  {:width$}  {:>6}  Samples",
+            "#", "Column", "Filled"
+        )?;
+        for (i, column) in self.columns.iter().enumerate() {
+            let rate = match self.rows {
+                0 => 0,
+                x => column.filled * 100 / x,
+            };
+            let samples: Vec<String> = column
+                .samples
+                .iter()
+                .map(|x| format!("\"{}\"", truncate(x)))
+                .collect();
+            let line = format!(
+                "{:>digits$}  {:width$}  {:>5}%  {}",
+                i + 1,
+                column.name,
+                rate,
+                samples.join(", ")
+            );
+            writeln!(out, "{}", line.trim_end())?;
+        }
+        writeln!(out)?;
+
+        let mapping = self.guess_mapping();
+        if mapping.is_empty() {
+            return writeln!(out, "No field mapping could be guessed.");
+        }
+        writeln!(out, "Guessed mapping:")?;
+        for x in mapping {
+            let line = format!("    --field-mapping \"{}={}\"", x.field, x.template);
+            match x.fuzzy {
+                true => writeln!(out, "{}  (similar name)", line)?,
+                false => writeln!(out, "{}", line)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Truncate long sample values and replace line breaks by spaces
+fn truncate(value: &str) -> String {
+    let value = value.replace(['\r', '\n'], " ");
+    match value.chars().count() > SAMPLE_LENGTH {
+        true => format!(
+            "{}...",
+            value.chars().take(SAMPLE_LENGTH - 3).collect::<String>()
+        ),
+        false => value,
+    }
+}
+
+/// Inspect all input files of the config and print the results
+pub fn run<W: std::io::Write>(config: &args::Config, out: &mut W) -> Result<(), Error> {
+    let mut files_input = Vec::new();
+    for pattern in &config.files_input {
+        files_input.extend(glob::expand(&pattern.to_string_lossy())?);
+    }
+
+    for (i, path) in files_input.iter().enumerate() {
+        let file_input = std::fs::File::open(path)
+            .map_err(|e| Error::io(format!("Could not open csv file: {}", path.display()), e))?;
+        let mut reader =
+            csvreader::Reader::new(&file_input, &config.csv_delimiter, config.csv_lazy)
+                .with_skip(config.skip_rows);
+        if let Some(x) = config.limit_rows {
+            reader = reader.with_limit(x);
+        }
+
+        let inspection = Inspection::new(reader);
+        if i > 0 {
+            writeln!(out).map_err(|e| Error::io(String::from("Could not write output"), e))?;
+        }
+        inspection
+            .write(out, &path.display().to_string())
+            .map_err(|e| Error::io(String::from("Could not write output"), e))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CSV: &str = "\
+Title,Authors,PY,Document Type
+First Title,\"Doe, Jane\",2021,Article
+Second Title,,2021,Article
+A very long title that does not fit into the table at all,\"Roe, Richard\",,Book
+";
+
+    #[test]
+    fn test_inspection() {
+        let inspection = Inspection::new(csvreader::Reader::new(CSV.as_bytes(), ",", false));
+        assert_eq!(inspection.rows, 3);
+        assert_eq!(inspection.columns[1].filled, 2);
+        assert_eq!(inspection.columns[2].samples, vec![String::from("2021")]);
+        let fields: Vec<String> = inspection
+            .guess_mapping()
+            .into_iter()
+            .map(|x| x.field)
+            .collect();
+        assert_eq!(fields, vec!["title", "author", "year", "entrytype"]);
+
+        let mut out = Vec::new();
+        inspection.write(&mut out, "test.csv").unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
+File \"test.csv\": 3 rows, 4 columns
+
+#  Column         Filled  Samples
+1  Title            100%  \"First Title\", \"Second Title\", \"A very long title that does not fit i...\"
+2  Authors           66%  \"Doe, Jane\", \"Roe, Richard\"
+3  PY                66%  \"2021\"
+4  Document Type    100%  \"Article\", \"Book\"
+
+Guessed mapping:
+    --field-mapping \"title=[[Title]]\"
+    --field-mapping \"author=[[Authors]]\"
+    --field-mapping \"year=[[PY]]\"
+    --field-mapping \"entrytype=[[Document Type]]\"
+"
+        );
+    }
+}
@@ -0,0 +1,230 @@
+use log::debug;
+
+use crate::Error;
+
+/// Fields that contain the title of a journal
+const JOURNAL_FIELDS: [&str; 2] = ["journal", "journaltitle"];
+
+/// Form of journal titles in the output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JournalForm {
+    /// Full titles like "Journal of the American Chemical Society"
+    #[default]
+    Full,
+    /// Abbreviated titles like "J. Am. Chem. Soc."
+    Abbreviated,
+    /// Full titles, and abbreviated titles in the field `shortjournal`
+    Both,
+}
+
+/// List of journal titles and their abbreviations, e.g., the ISO 4 abbreviations of JabRef
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JournalList {
+    // Full and abbreviated titles
+    journals: Vec<(String, String)>,
+    // Position in `journals` by the normalized full or abbreviated title
+    index: std::collections::HashMap<String, usize>,
+}
+
+impl JournalList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a list of journals in the CSV format of JabRef, i.e., one journal per line with the
+    /// full and the abbreviated title separated by ";". Further columns are ignored. Lines like
+    /// `Full Title = Abbrev. Title` of older JabRef lists are supported, too.
+    pub fn with_file(self, path: &std::path::Path) -> Result<Self, Error> {
+        let file = std::fs::File::open(path).map_err(|e| {
+            Error::io(
+                format!("Could not open journal list: {}", path.display()),
+                e,
+            )
+        })?;
+        self.with_reader(file, &path.display().to_string())
+    }
+
+    /// Read a list of journals, see `with_file`
+    fn with_reader<R: std::io::Read>(mut self, reader: R, name: &str) -> Result<Self, Error> {
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(b';')
+            .has_headers(false)
+            .flexible(true)
+            .comment(Some(b'#'))
+            .from_reader(reader);
+
+        for record in reader.records() {
+            let record = record.map_err(|e| {
+                Error::Config(format!("Could not read journal list \"{}\": {}", name, e))
+            })?;
+            let line = record.position().map_or(0, |x| x.line());
+            let (full, abbreviation) = match (record.get(0), record.get(1)) {
+                (Some(full), Some(abbreviation)) => (full, abbreviation),
+                (Some(x), None) if x.trim().is_empty() => continue,
+                (Some(x), None) => x.split_once(" = ").unwrap_or((x, "")),
+                _ => continue,
+            };
+            let (full, abbreviation) = (full.trim(), abbreviation.trim());
+            if full.is_empty() || abbreviation.is_empty() {
+                return Err(Error::Config(format!(
+                    "Invalid journal in line {} of \"{}\": {}",
+                    line,
+                    name,
+                    record.iter().collect::<Vec<&str>>().join(";")
+                )));
+            }
+            self.add(full, abbreviation);
+        }
+
+        Ok(self)
+    }
+
+    /// Add a journal. If a title is in the list already, the first journal is kept.
+    pub fn add(&mut self, full: &str, abbreviation: &str) {
+        let i = self.journals.len();
+        self.journals
+            .push((String::from(full), String::from(abbreviation)));
+        self.index.entry(normalize(full)).or_insert(i);
+        self.index.entry(normalize(abbreviation)).or_insert(i);
+    }
+
+    /// Number of journals in the list
+    pub fn len(&self) -> usize {
+        self.journals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.journals.is_empty()
+    }
+
+    /// Full and abbreviated title of a journal given by one of both. Case, dots, and whitespace
+    /// are ignored.
+    pub fn lookup(&self, title: &str) -> Option<(&str, &str)> {
+        self.index
+            .get(&normalize(title))
+            .map(|i| (self.journals[*i].0.as_str(), self.journals[*i].1.as_str()))
+    }
+
+    /// Replace the journal title of an entry by its full or abbreviated form. Titles that are not
+    /// in the list are kept as they are.
+    pub fn apply(&self, entry: &mut biblatex::Entry, form: JournalForm) {
+        for field in JOURNAL_FIELDS {
+            let title = match entry.get(field) {
+                Some(x) => biblatex::ChunksExt::format_verbatim(x),
+                None => continue,
+            };
+            let (full, abbreviation) = match self.lookup(&title) {
+                Some(x) => x,
+                None => {
+                    debug!("Journal \"{}\" is not in the journal list.", title);
+                    continue;
+                }
+            };
+
+            let value = match form {
+                JournalForm::Abbreviated => abbreviation,
+                JournalForm::Full | JournalForm::Both => full,
+            };
+            entry.set(field, normal(value));
+            if form == JournalForm::Both && entry.get("shortjournal").is_none() {
+                entry.set("shortjournal", normal(abbreviation));
+            }
+        }
+    }
+}
+
+/// Normalize a title for comparisons, e.g., "J. Am. Chem. Soc." to "j am chem soc"
+fn normalize(title: &str) -> String {
+    title
+        .to_lowercase()
+        .replace('.', " ")
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn normal(value: &str) -> biblatex::Chunks {
+    vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+        String::from(value),
+    ))]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIST: &str = "\
+# JabRef abbreviations
+Journal of the American Chemical Society;J. Am. Chem. Soc.;JACS
+\"Physics; Letters\";Phys. Lett.
+
+Nature = Nature
+";
+
+    #[test]
+    fn test_read_list() {
+        let list = JournalList::new()
+            .with_reader(LIST.as_bytes(), "list.csv")
+            .unwrap();
+        assert_eq!(list.len(), 3);
+        assert_eq!(
+            list.lookup("journal of the american chemical society"),
+            Some((
+                "Journal of the American Chemical Society",
+                "J. Am. Chem. Soc."
+            ))
+        );
+        assert_eq!(
+            list.lookup("J Am Chem Soc"),
+            Some((
+                "Journal of the American Chemical Society",
+                "J. Am. Chem. Soc."
+            ))
+        );
+        assert_eq!(
+            list.lookup("Phys. Lett."),
+            Some(("Physics; Letters", "Phys. Lett."))
+        );
+        assert_eq!(list.lookup("Science"), None);
+
+        assert!(JournalList::new()
+            .with_reader("Only a title\n".as_bytes(), "list.csv")
+            .is_err());
+    }
+
+    #[test]
+    fn test_apply() {
+        let list = JournalList::new()
+            .with_reader(LIST.as_bytes(), "list.csv")
+            .unwrap();
+        let mut entry = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        entry.set("journal", normal("J. Am. Chem. Soc."));
+
+        let value = |entry: &biblatex::Entry, field: &str| {
+            entry.get(field).map(biblatex::ChunksExt::format_verbatim)
+        };
+
+        let mut full = entry.clone();
+        list.apply(&mut full, JournalForm::Full);
+        assert_eq!(
+            value(&full, "journal").as_deref(),
+            Some("Journal of the American Chemical Society")
+        );
+        assert_eq!(value(&full, "shortjournal"), None);
+
+        let mut both = full.clone();
+        list.apply(&mut both, JournalForm::Both);
+        assert_eq!(value(&both, "journal"), value(&full, "journal"));
+        assert_eq!(
+            value(&both, "shortjournal").as_deref(),
+            Some("J. Am. Chem. Soc.")
+        );
+
+        let mut abbreviated = both.clone();
+        list.apply(&mut abbreviated, JournalForm::Abbreviated);
+        assert_eq!(
+            value(&abbreviated, "journal").as_deref(),
+            Some("J. Am. Chem. Soc.")
+        );
+    }
+}
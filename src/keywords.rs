@@ -0,0 +1,97 @@
+/// Separators of keywords that are used if none are given
+const DEFAULT_SEPARATORS: [&str; 3] = [";", ",", "|"];
+
+/// Split lists of keywords like "IoT; Security | iot", remove duplicates, and join them with ", "
+/// as BibLaTeX expects
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeywordNormalizer {
+    // Strings that separate keywords
+    separators: Vec<String>,
+    // Convert all keywords to lowercase
+    lowercase: bool,
+}
+
+impl KeywordNormalizer {
+    /// Create a new normalizer. If `separators` is empty, keywords are separated by ";", ",", or
+    /// "|".
+    pub fn new(separators: &[String]) -> Self {
+        let separators: Vec<String> = match separators.is_empty() {
+            true => DEFAULT_SEPARATORS
+                .iter()
+                .map(|x| String::from(*x))
+                .collect(),
+            false => separators.to_vec(),
+        };
+        Self {
+            separators,
+            lowercase: false,
+        }
+    }
+
+    pub fn with_lowercase(mut self) -> Self {
+        self.lowercase = true;
+        self
+    }
+
+    /// Is the field a list of keywords?
+    pub fn is_keyword_field(field: &str) -> bool {
+        field == "keywords"
+    }
+
+    /// Split a list of keywords, trim them, and remove empty keywords and duplicates (ignoring
+    /// case). The first spelling of a duplicate is kept.
+    pub fn split(&self, input: &str) -> Vec<String> {
+        let mut keywords = vec![String::from(input)];
+        for separator in self.separators.iter().filter(|x| !x.is_empty()) {
+            keywords = keywords
+                .iter()
+                .flat_map(|x| x.split(separator.as_str()))
+                .map(String::from)
+                .collect();
+        }
+
+        let mut ret: Vec<String> = Vec::new();
+        for keyword in keywords {
+            let keyword = keyword.split_whitespace().collect::<Vec<&str>>().join(" ");
+            let keyword = match self.lowercase {
+                true => keyword.to_lowercase(),
+                false => keyword,
+            };
+            if keyword.is_empty()
+                || ret
+                    .iter()
+                    .any(|x| x.to_lowercase() == keyword.to_lowercase())
+            {
+                continue;
+            }
+            ret.push(keyword);
+        }
+        ret
+    }
+
+    /// Normalize a list of keywords, see `split`. The keywords are joined with ", ".
+    pub fn normalize(&self, input: &str) -> String {
+        self.split(input).join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        let normalizer = KeywordNormalizer::new(&[]);
+        assert_eq!(
+            normalizer.normalize(" IoT; Security | iot,,  smart   office ;"),
+            "IoT, Security, smart office"
+        );
+        assert_eq!(normalizer.normalize(" ; "), "");
+
+        let normalizer = KeywordNormalizer::new(&[String::from(" / ")]).with_lowercase();
+        assert_eq!(
+            normalizer.normalize("Machine Learning / TCP/IP / machine learning"),
+            "machine learning, tcp/ip"
+        );
+    }
+}
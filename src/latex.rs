@@ -0,0 +1,280 @@
+use unicode_normalization::char::{compose, decompose_canonical};
+
+/// Combining characters and their LaTeX accent commands
+const ACCENTS: [(char, &str); 15] = [
+    ('\u{0300}', "`"),
+    ('\u{0301}', "'"),
+    ('\u{0302}', "^"),
+    ('\u{0303}', "~"),
+    ('\u{0304}', "="),
+    ('\u{0306}', "u"),
+    ('\u{0307}', "."),
+    ('\u{0308}', "\""),
+    ('\u{030A}', "r"),
+    ('\u{030B}', "H"),
+    ('\u{030C}', "v"),
+    ('\u{0323}', "d"),
+    ('\u{0327}', "c"),
+    ('\u{0328}', "k"),
+    ('\u{0331}', "b"),
+];
+
+/// Characters that are written as a LaTeX command on their own
+const SYMBOLS: [(char, &str); 37] = [
+    ('ß', "{\\ss}"),
+    ('æ', "{\\ae}"),
+    ('Æ', "{\\AE}"),
+    ('œ', "{\\oe}"),
+    ('Œ', "{\\OE}"),
+    ('ø', "{\\o}"),
+    ('Ø', "{\\O}"),
+    ('å', "{\\aa}"),
+    ('Å', "{\\AA}"),
+    ('ł', "{\\l}"),
+    ('Ł', "{\\L}"),
+    ('ı', "{\\i}"),
+    ('ȷ', "{\\j}"),
+    ('ð', "{\\dh}"),
+    ('Ð', "{\\DH}"),
+    ('þ', "{\\th}"),
+    ('Þ', "{\\TH}"),
+    ('\u{00A0}', "~"),
+    ('–', "--"),
+    ('—', "---"),
+    ('‘', "`"),
+    ('’', "'"),
+    ('“', "``"),
+    ('”', "''"),
+    ('„', "{\\quotedblbase}"),
+    ('«', "{\\guillemotleft}"),
+    ('»', "{\\guillemotright}"),
+    ('…', "{\\ldots}"),
+    ('§', "{\\S}"),
+    ('¶', "{\\P}"),
+    ('©', "{\\textcopyright}"),
+    ('®', "{\\textregistered}"),
+    ('™', "{\\texttrademark}"),
+    ('€', "{\\texteuro}"),
+    ('£', "{\\pounds}"),
+    ('°', "{\\textdegree}"),
+    ('¿', "{?`}"),
+];
+
+/// Characters that are written in math mode
+const MATH_SYMBOLS: [(char, &str); 48] = [
+    ('α', "\\alpha"),
+    ('β', "\\beta"),
+    ('γ', "\\gamma"),
+    ('δ', "\\delta"),
+    ('ε', "\\epsilon"),
+    ('ζ', "\\zeta"),
+    ('η', "\\eta"),
+    ('θ', "\\theta"),
+    ('ι', "\\iota"),
+    ('κ', "\\kappa"),
+    ('λ', "\\lambda"),
+    ('μ', "\\mu"),
+    ('µ', "\\mu"),
+    ('ν', "\\nu"),
+    ('ξ', "\\xi"),
+    ('π', "\\pi"),
+    ('ρ', "\\rho"),
+    ('σ', "\\sigma"),
+    ('τ', "\\tau"),
+    ('υ', "\\upsilon"),
+    ('φ', "\\phi"),
+    ('χ', "\\chi"),
+    ('ψ', "\\psi"),
+    ('ω', "\\omega"),
+    ('Γ', "\\Gamma"),
+    ('Δ', "\\Delta"),
+    ('Θ', "\\Theta"),
+    ('Λ', "\\Lambda"),
+    ('Ξ', "\\Xi"),
+    ('Π', "\\Pi"),
+    ('Σ', "\\Sigma"),
+    ('Φ', "\\Phi"),
+    ('Ψ', "\\Psi"),
+    ('Ω', "\\Omega"),
+    ('±', "\\pm"),
+    ('×', "\\times"),
+    ('÷', "\\div"),
+    ('≤', "\\leq"),
+    ('≥', "\\geq"),
+    ('≠', "\\neq"),
+    ('≈', "\\approx"),
+    ('∞', "\\infty"),
+    ('→', "\\rightarrow"),
+    ('←', "\\leftarrow"),
+    ('∑', "\\sum"),
+    ('√', "\\surd"),
+    ('∈', "\\in"),
+    ('·', "\\cdot"),
+];
+
+/// Convert a single character into a LaTeX command. Returns `None` for ASCII characters and
+/// characters that are unknown.
+pub fn encode_char(c: char) -> Option<String> {
+    if c.is_ascii() {
+        return None;
+    }
+    if let Some(x) = SYMBOLS.iter().find(|x| x.0 == c) {
+        return Some(String::from(x.1));
+    }
+    if let Some(x) = MATH_SYMBOLS.iter().find(|x| x.0 == c) {
+        return Some(format!("{{${}$}}", x.1));
+    }
+
+    // accented characters: split into base character and accents
+    let mut parts = Vec::new();
+    decompose_canonical(c, |x| parts.push(x));
+    let (base, accents) = parts.split_first()?;
+    if accents.is_empty() || !base.is_ascii_alphabetic() {
+        return None;
+    }
+
+    let mut ret = match base {
+        'i' => String::from("\\i"),
+        'j' => String::from("\\j"),
+        x => x.to_string(),
+    };
+    for accent in accents {
+        let command = ACCENTS.iter().find(|x| x.0 == *accent)?.1;
+        ret = if command.chars().all(|x| x.is_ascii_alphabetic()) {
+            format!("\\{}{{{}}}", command, ret)
+        } else {
+            format!("\\{}{}", command, ret)
+        };
+    }
+
+    Some(format!("{{{}}}", ret))
+}
+
+/// Decode LaTeX commands for accents and special characters into unicode characters
+pub struct Decoder {
+    // Accent commands, e.g., `{\"u}`, `\"{u}`, `\"u`, or `\v{c}`
+    accents: regex::Regex,
+    // Special characters, e.g., `{\ss}`, `\ss{}`, `$\alpha$`, or `\&`
+    symbols: regex::Regex,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder {
+    pub fn new() -> Self {
+        Self {
+            accents: regex::Regex::new(concat!(
+                "\\{\\\\([`'^~=.\"])\\s*\\{?(\\\\[ij]|[[:alpha:]])\\}?\\}",
+                "|\\{\\\\([uHvdckbr])\\s*(?:\\{(\\\\[ij]|[[:alpha:]])\\}|\\s([[:alpha:]]))\\}",
+                "|\\\\([`'^~=.\"])\\s*(?:\\{(\\\\[ij]|[[:alpha:]])\\}|(\\\\[ij]|[[:alpha:]]))",
+                "|\\\\([uHvdckbr])(?:\\{(\\\\[ij]|[[:alpha:]])\\}|\\s([[:alpha:]]))",
+            ))
+            .unwrap(),
+            symbols: regex::Regex::new(concat!(
+                "\\{\\\\([[:alpha:]]+)\\}",
+                "|\\$\\\\([[:alpha:]]+)\\$",
+                "|\\\\([[:alpha:]]+)(?:\\{\\}|\\s|\\b)",
+                "|\\\\([&%$_#])",
+            ))
+            .unwrap(),
+        }
+    }
+
+    /// Replace all known LaTeX commands in `input` by unicode characters. Unknown commands are
+    /// kept as they are.
+    pub fn decode(&self, input: &str) -> String {
+        if !input.contains('\\') {
+            return String::from(input);
+        }
+
+        let ret = self.accents.replace_all(input, |caps: &regex::Captures| {
+            let groups: Vec<&str> = caps.iter().skip(1).flatten().map(|x| x.as_str()).collect();
+            let (command, base) = (groups[0], groups[1]);
+            let base = match base {
+                "\\i" => 'i',
+                "\\j" => 'j',
+                x => x.chars().next().unwrap(),
+            };
+            match ACCENTS.iter().find(|x| x.1 == command) {
+                Some(accent) => compose(base, accent.0)
+                    .map(|x| x.to_string())
+                    .unwrap_or_else(|| format!("{}{}", base, accent.0)),
+                None => String::from(&caps[0]),
+            }
+        });
+
+        let ret = self.symbols.replace_all(&ret, |caps: &regex::Captures| {
+            let command = caps
+                .iter()
+                .skip(1)
+                .flatten()
+                .next()
+                .map(|x| x.as_str())
+                .unwrap();
+            if command.len() == 1 && !command.chars().all(|x| x.is_ascii_alphabetic()) {
+                return String::from(command);
+            }
+            let symbol = SYMBOLS
+                .iter()
+                .find(|x| x.1.trim_start_matches("{\\").trim_end_matches('}') == command)
+                .or_else(|| {
+                    MATH_SYMBOLS
+                        .iter()
+                        .find(|x| x.1.trim_start_matches('\\') == command)
+                });
+            match symbol {
+                Some(x) => x.0.to_string(),
+                None => String::from(&caps[0]),
+            }
+        });
+
+        ret.into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(input: &str) -> String {
+        input
+            .chars()
+            .map(|c| encode_char(c).unwrap_or_else(|| c.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("Müller"), "M{\\\"u}ller");
+        assert_eq!(encode("Straße"), "Stra{\\ss}e");
+        assert_eq!(encode("Dvořák"), "Dvo{\\v{r}}{\\'a}k");
+        assert_eq!(encode("naïve"), "na{\\\"\\i}ve");
+        assert_eq!(encode("1990–2000"), "1990--2000");
+        assert_eq!(encode("α ≤ 5"), "{$\\alpha$} {$\\leq$} 5");
+        assert_eq!(encode("plain ascii"), "plain ascii");
+        assert_eq!(encode("日本"), "日本");
+    }
+
+    #[test]
+    fn test_decode() {
+        let decoder = Decoder::new();
+        assert_eq!(decoder.decode("M{\\\"u}ller"), "Müller");
+        assert_eq!(decoder.decode("M\\\"{u}ller"), "Müller");
+        assert_eq!(decoder.decode("M\\\"uller"), "Müller");
+        assert_eq!(decoder.decode("Stra{\\ss}e"), "Straße");
+        assert_eq!(decoder.decode("Stra\\ss{}e"), "Straße");
+        assert_eq!(decoder.decode("Dvo{\\v{r}}\\'ak"), "Dvořák");
+        assert_eq!(decoder.decode("na{\\\"\\i}ve"), "naïve");
+        assert_eq!(decoder.decode("$\\alpha$-helix"), "α-helix");
+        assert_eq!(
+            decoder.decode("Smith \\& Wesson, 100\\%"),
+            "Smith & Wesson, 100%"
+        );
+        assert_eq!(decoder.decode("\\unknown{x}"), "\\unknown{x}");
+        assert_eq!(decoder.decode("Hello"), "Hello");
+    }
+}
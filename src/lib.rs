@@ -1,69 +1,600 @@
-use anyhow::{anyhow, Context};
-use log::{error, info};
+use log::{error, info, warn};
 
 pub mod args;
 pub mod bibwriter;
 pub mod converter;
 pub mod csvreader;
+pub mod date;
+pub mod dialect;
+mod error;
+pub mod filter;
+pub mod glob;
+pub mod group;
+pub mod identifiers;
+pub mod inspect;
+pub mod journals;
+pub mod keywords;
+pub mod latex;
+pub mod macros;
+pub mod pages;
+pub mod policy;
+pub mod profile;
+pub mod protect;
+pub mod rejects;
+pub mod report;
+pub mod sort;
+pub mod split;
+pub mod suggest;
+pub mod wizard;
 
-pub fn run(config: &args::Config) -> Result<(), anyhow::Error> {
-    // open file for reading and writing
-    let file_input = std::fs::File::open(&config.file_input)
-        .with_context(|| format!("Could not open csv file: {}", &config.file_input.display()))?;
-    let buf_output =
-        std::io::BufWriter::new(std::fs::File::create(&config.file_output).with_context(|| {
-            format!(
-                "Could not open file for writing: {}",
-                &config.file_output.display()
-            )
-        })?);
-    info!(
-        "Created file \"{}\" to write output.",
-        &config.file_output.display()
-    );
+pub use error::Error;
+
+/// Summary of a conversion run
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// Number of rows selected from the CSV file, including rejected rows
+    pub rows: usize,
+    /// Number of entries written to the output file
+    pub entries: usize,
+    /// Number of rows that could not be converted
+    pub rejected: usize,
+    /// Number of rows that were skipped or didn't match the filter
+    pub filtered: usize,
+}
+
+pub fn run(config: &args::Config) -> Result<Summary, Error> {
+    // find all input files
+    let mut files_input = Vec::new();
+    for pattern in &config.files_input {
+        files_input.extend(glob::expand(&pattern.to_string_lossy())?);
+    }
+    if !config.dry_run {
+        check_output(&config.file_output, &files_input)?;
+        if config.file_rejects.is_some() {
+            check_rejects_columns(config, &files_input)?;
+        }
+    }
 
-    // create new csvparser, converter, and writer
+    // create new converter and writer
     //
     // TODO cloning here makes absolutely no sense!
     let mut csv_field_mapping = config.csv_field_mapping.clone();
+    if let Some(path) = &config.file_profile {
+        profile::Profile::from_file(path)?.apply(&mut csv_field_mapping);
+    }
+    // the mapping without defaults and guesses, only these columns have to exist. The guesses
+    // are based on the first input file, other files may lack some of the guessed columns.
+    let checked_mapping = csv_field_mapping.clone();
+    if config.suggest_mapping {
+        if let Some(headers) = suggest::read_headers(config)? {
+            let suggestions = suggest::suggest(&headers, &csv_field_mapping);
+            for x in &suggestions {
+                info!("Suggested mapping: {}={}", x.field, x.template);
+            }
+            suggest::apply(&suggestions, &mut csv_field_mapping);
+        }
+    }
+    // the report covers all mapped fields, including the guessed ones
+    let mapped_fields: Vec<String> = csv_field_mapping.keys().cloned().collect();
     let mut verbatim_fields = config.verbatim_fields.clone();
-    let reader = csvreader::Reader::new(&file_input, &config.csv_delimiter, config.csv_lazy);
-    let converter = {
-        let mut ret = converter::FieldConverter::new(&mut csv_field_mapping, &mut verbatim_fields);
-        if config.mapping_defaults {
-            ret = ret.add_defaults()
+    let filter = config
+        .filter
+        .as_deref()
+        .map(filter::Filter::new)
+        .transpose()?;
+    let sorter = config
+        .sort_by
+        .as_deref()
+        .map(sort::EntrySorter::new)
+        .transpose()?;
+    let converter = create_converter(config, &mut csv_field_mapping, &mut verbatim_fields)?;
+    // frequent values are only known after all rows are read, macros of a file apply to
+    // journals unless other fields are given
+    let frequent_strings = !config.string_fields.is_empty();
+    let mut string_macros = {
+        let mut ret = match (frequent_strings, &config.file_strings) {
+            (false, Some(_)) => macros::StringMacros::new(&[String::from("journal")]),
+            _ => macros::StringMacros::new(&config.string_fields),
+        };
+        if let Some(x) = &config.file_strings {
+            ret = ret.with_file(x)?;
+        }
+        if config.month_macros {
+            ret = ret.with_months();
         }
         ret
     };
-    let mut writer: Box<dyn bibwriter::BibWrite> = match config.output_type {
-        args::OutputType::Bibtex => Box::new(bibwriter::BibtexWriter::new(buf_output)),
-        args::OutputType::Biblatex => Box::new(bibwriter::BiblatexWriter::new(buf_output)),
+    // a dry run prints all entries, even if they would be split into several files
+    let mut writer: Box<dyn bibwriter::BibWrite> = match &config.split_by {
+        Some(field) if !config.dry_run => {
+            let template = config.file_output.to_string_lossy().into_owned();
+            let config = config.clone();
+            let verbatim_fields = converter.verbatim_fields().to_vec();
+            Box::new(split::SplitWriter::new(
+                field,
+                &template,
+                Box::new(move |path, append| {
+                    create_writer(&config, path, &verbatim_fields, append)
+                }),
+            )?)
+        }
+        _ => create_writer(
+            config,
+            &config.file_output,
+            converter.verbatim_fields(),
+            false,
+        )?,
+    };
+
+    if !frequent_strings && !string_macros.is_empty() {
+        writer.set_macros(&string_macros)?;
+    }
+
+    // the rejects file gets the columns of the first input file, all files have the same columns
+    let mut rejects_file = match &config.file_rejects {
+        Some(path) if !config.dry_run => Some(std::fs::File::create(path).map_err(|e| {
+            Error::io(
+                format!("Could not open file for writing: {}", path.display()),
+                e,
+            )
+        })?),
+        _ => None,
     };
+    let mut rejects = None;
 
     // main loop
     let start = std::time::Instant::now();
+    let mut summary = Summary::default();
+    let mut report = config
+        .file_report
+        .as_ref()
+        .map(|_| report::Report::new(&mapped_fields));
+    let policy =
+        policy::ErrorPolicy::new(config.csv_lazy, config.max_errors, config.max_error_rate);
+    let mut grouper = config
+        .group_by
+        .as_ref()
+        .map(|column| group::Grouper::new(column, &config.aggregations, config.group_consecutive));
+    // entries to sort or to count values for string macros are kept until all rows are read
+    let mut buffer = Vec::new();
 
-    for (index, entry) in reader.enumerate() {
-        let entry = match entry {
-            Ok(x) => x,
-            Err(e) => {
-                if config.csv_lazy {
-                    error!("Error converting item: {}. Skipping item.", e);
+    for path in &files_input {
+        if config.limit_rows.is_some_and(|x| summary.rows >= x) {
+            break;
+        }
+
+        // create new csvparser, every file has its own header
+        let file_input = std::fs::File::open(path)
+            .map_err(|e| Error::io(format!("Could not open csv file: {}", path.display()), e))?;
+        let mut reader =
+            csvreader::Reader::new(&file_input, &config.csv_delimiter, policy.allows_errors())
+                .with_skip(config.skip_rows);
+        if let Some(x) = &filter {
+            reader = reader.with_filter(x.clone());
+        }
+        if let Some(x) = config.limit_rows {
+            reader = reader.with_limit(x - summary.rows);
+        }
+        check_columns(config, &checked_mapping, reader.headers(), filter.as_ref())?;
+
+        if let Some(file) = rejects_file.take() {
+            rejects = Some(rejects::RejectWriter::new(
+                std::io::BufWriter::new(file),
+                reader.headers(),
+                &config.csv_delimiter,
+                files_input.len() > 1,
+            )?);
+        }
+        if let Some(x) = rejects.as_mut() {
+            x.set_source(&path.display().to_string());
+        }
+
+        let rows_before = summary.rows;
+        for record in reader.by_ref() {
+            let index = summary.rows;
+            summary.rows += 1;
+
+            // convert the row and write it, unless it waits for further rows of its group
+            let record = match record {
+                Ok(x) => x,
+                Err(e) => {
+                    let (line, byte, raw) = (e.line, e.byte, e.raw.clone());
+                    let e = Error::from(e);
+                    if let Some(x) = report.as_mut() {
+                        x.add_skipped(line, &reason(&e));
+                    }
+                    reject(e, line, byte, &raw, &mut rejects, &mut summary, &policy)?;
                     continue;
-                } else {
-                    return Err(anyhow!("{}. Option \"-l\" might help.", e));
                 }
+            };
+            let key = grouper.as_ref().and_then(|x| x.key(&record.fields));
+            let (mut entry, messages) =
+                converter.convert_fields_with_messages(record.fields, format!("entry_{index}"));
+            for message in messages {
+                warn!("Line {}: {}", record.line, message);
             }
-        };
-        let entry = converter.convert_fields(entry, format!("entry_{index}"));
-        writer.write(&entry)?;
+            if let Some(field) = &config.source_field {
+                entry.set(
+                    field,
+                    vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                        path.display().to_string(),
+                    ))],
+                );
+            }
+            let row = group::ConvertedRow {
+                line: record.line,
+                byte: record.byte,
+                raw: record.raw,
+                entry,
+            };
+            let rows = match grouper.as_mut() {
+                Some(x) => x.add(key, row),
+                None => vec![row],
+            };
+            if sorter.is_some() || frequent_strings {
+                buffer.extend(rows);
+                continue;
+            }
+            for row in rows {
+                write_row(
+                    writer.as_mut(),
+                    row,
+                    &mut rejects,
+                    &mut summary,
+                    &policy,
+                    &mut report,
+                )?;
+            }
+        }
+        summary.filtered += reader.get_num_filtered_rows();
+
+        if files_input.len() > 1 {
+            info!(
+                "Read {} rows from \"{}\".",
+                summary.rows - rows_before,
+                path.display()
+            );
+        }
     }
+    if let Some(x) = grouper {
+        buffer.extend(x.finish());
+    }
+    if let Some(x) = &sorter {
+        x.sort(&mut buffer, |row| &row.entry);
+    }
+    if frequent_strings {
+        string_macros.add_frequent(buffer.iter().map(|x| &x.entry), config.string_min_count);
+        writer.set_macros(&string_macros)?;
+    }
+    for row in buffer {
+        write_row(
+            writer.as_mut(),
+            row,
+            &mut rejects,
+            &mut summary,
+            &policy,
+            &mut report,
+        )?;
+    }
+    if let Some(rejects) = rejects.as_mut() {
+        rejects.flush()?;
+    }
+    summary.entries = writer.get_num_written_entries();
+
     info!(
-        "Wrote {} entries in {:?}.",
-        writer.get_num_written_entries(),
-        start.elapsed()
+        "Wrote {} entries in {:?}, skipped {} of {} rows because of errors.",
+        summary.entries,
+        start.elapsed(),
+        summary.rejected,
+        summary.rows
     );
+    if summary.filtered > 0 || config.limit_rows.is_some() {
+        info!(
+            "Selected {} rows, filtered out {} rows.",
+            summary.rows, summary.filtered
+        );
+    }
+    for (path, entries) in writer.get_written_files() {
+        info!("Wrote {} entries to \"{}\".", entries, path.display());
+    }
+    if let (Some(rejects), Some(path)) = (&rejects, &config.file_rejects) {
+        info!(
+            "Wrote {} rejected rows to \"{}\".",
+            rejects.get_num_written_rows(),
+            path.display()
+        );
+    }
+
+    if let (Some(report), Some(path)) = (report.as_mut(), &config.file_report) {
+        report.set_summary(&summary);
+        report.set_renamed_keys(writer.get_renamed_keys());
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path).map_err(|e| {
+            Error::io(
+                format!("Could not open file for writing: {}", path.display()),
+                e,
+            )
+        })?);
+        report
+            .write(&mut file, config.report_format)
+            .map_err(|e| Error::io(format!("Could not write report: {}", path.display()), e))?;
+        info!("Wrote report to \"{}\".", path.display());
+    }
+
+    if !policy.is_tolerated(summary.rows, summary.rejected, true) {
+        return Err(Error::TooManyErrors {
+            errors: summary.rejected,
+            rows: summary.rows,
+        });
+    }
 
     // the end
+    Ok(summary)
+}
+
+/// Create a converter with all conversions of the config, e.g., date parsing
+pub(crate) fn create_converter<'a>(
+    config: &args::Config,
+    csv_field_mapping: &'a mut std::collections::HashMap<String, String>,
+    verbatim_fields: &'a mut Vec<String>,
+) -> Result<converter::FieldConverter<'a>, Error> {
+    let mut ret = converter::FieldConverter::new(csv_field_mapping, verbatim_fields)?;
+    if config.mapping_defaults {
+        ret = ret.add_defaults()
+    }
+    if config.passthrough {
+        ret = ret.with_passthrough(&config.passthrough_exclude);
+    }
+    if config.parse_dates {
+        ret = ret.with_date_parser(date::DateParser::new(
+            &config.date_formats,
+            config.date_order,
+        )?);
+    }
+    if config.latex_decode {
+        ret = ret.with_latex_decoding();
+    }
+    if config.protect_titles {
+        ret = ret.with_title_protection(&config.protected_words);
+    }
+    if config.normalize_pages {
+        ret = ret.with_page_normalization();
+    }
+    if config.normalize_keywords {
+        let mut normalizer = keywords::KeywordNormalizer::new(&config.keyword_separators);
+        if config.lowercase_keywords {
+            normalizer = normalizer.with_lowercase();
+        }
+        ret = ret.with_keyword_normalization(normalizer);
+    }
+    if config.normalize_identifiers {
+        let mut normalizer = identifiers::IdentifierNormalizer::new();
+        if config.isbn13 {
+            normalizer = normalizer.with_isbn13();
+        }
+        ret = ret.with_identifier_normalization(normalizer);
+    }
+    if !config.journal_lists.is_empty() {
+        let mut list = journals::JournalList::new();
+        for path in &config.journal_lists {
+            list = list.with_file(path)?;
+        }
+        info!("Read {} journals from the journal lists.", list.len());
+        ret = ret.with_journals(list, config.journal_form);
+    }
+    Ok(ret)
+}
+
+/// Check that the field mapping, the filter, and the grouping refer to existing columns only
+fn check_columns(
+    config: &args::Config,
+    mapping: &std::collections::HashMap<String, String>,
+    headers: &[String],
+    filter: Option<&filter::Filter>,
+) -> Result<(), Error> {
+    for (field, template) in mapping {
+        converter::check_template(template)?;
+        for column in converter::template_columns(template) {
+            if !headers.contains(&column) {
+                let e = Error::UnknownColumn {
+                    field: field.clone(),
+                    column,
+                };
+                if config.csv_lazy {
+                    warn!("{}.", e);
+                } else {
+                    return Err(e);
+                }
+            }
+        }
+    }
+    if let Some(column) = &config.group_by {
+        if !headers.contains(column) {
+            return Err(Error::Config(format!(
+                "Unknown CSV column to group by: \"{}\"",
+                column
+            )));
+        }
+    }
+    if let Some(x) = filter {
+        for column in x.columns() {
+            if !headers.contains(&column) {
+                warn!("Filter refers to unknown CSV column \"{}\".", column);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Refuse to overwrite an input file, e.g., one matched by a glob pattern, with the output
+fn check_output(
+    file_output: &std::path::Path,
+    files_input: &[std::path::PathBuf],
+) -> Result<(), Error> {
+    // an output file that doesn't exist yet cannot be an input file
+    let output = match std::fs::canonicalize(file_output) {
+        Ok(x) => x,
+        Err(_) => return Ok(()),
+    };
+    for path in files_input {
+        if std::fs::canonicalize(path).is_ok_and(|x| x == output) {
+            return Err(Error::Config(format!(
+                "Output file \"{}\" is an input file, too",
+                file_output.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The rejects file has the columns of the first input file, so all input files need the same
+/// columns
+fn check_rejects_columns(
+    config: &args::Config,
+    files_input: &[std::path::PathBuf],
+) -> Result<(), Error> {
+    let mut first: Option<Vec<String>> = None;
+    for path in files_input {
+        let file_input = std::fs::File::open(path)
+            .map_err(|e| Error::io(format!("Could not open csv file: {}", path.display()), e))?;
+        let headers = csvreader::Reader::new(&file_input, &config.csv_delimiter, config.csv_lazy)
+            .headers()
+            .to_vec();
+        match &first {
+            Some(x) if *x != headers => {
+                return Err(Error::Config(format!(
+                    "Columns of \"{}\" differ from the first input file, a rejects file needs the same columns in all files",
+                    path.display()
+                )))
+            }
+            Some(_) => {}
+            None => first = Some(headers),
+        }
+    }
     Ok(())
 }
+
+/// Create the output file, or append to it if `append` is set, and a writer for the output format.
+/// A dry run writes to stdout instead.
+fn create_writer(
+    config: &args::Config,
+    path: &std::path::Path,
+    verbatim_fields: &[String],
+    append: bool,
+) -> Result<Box<dyn bibwriter::BibWrite>, Error> {
+    if config.dry_run {
+        return Ok(format_writer(config, std::io::stdout(), verbatim_fields));
+    }
+    if let Some(x) = path.parent().filter(|x| !x.as_os_str().is_empty()) {
+        std::fs::create_dir_all(x)
+            .map_err(|e| Error::io(format!("Could not create directory: {}", x.display()), e))?;
+    }
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)
+        .map_err(|e| {
+            Error::io(
+                format!("Could not open file for writing: {}", path.display()),
+                e,
+            )
+        })?;
+    let buf_output = std::io::BufWriter::new(file);
+    if !append {
+        info!("Created file \"{}\" to write output.", path.display());
+    }
+
+    Ok(format_writer(config, buf_output, verbatim_fields))
+}
+
+/// Create a writer for the output format
+pub(crate) fn format_writer<'w, W: std::io::Write + 'w>(
+    config: &args::Config,
+    output: W,
+    verbatim_fields: &[String],
+) -> Box<dyn bibwriter::BibWrite + 'w> {
+    match config.output_type {
+        args::OutputType::Bibtex => {
+            let mut ret = bibwriter::BibtexWriter::new(output).with_format(config.format.clone());
+            if config.latex_encode {
+                ret = ret.with_latex_encoding(verbatim_fields);
+            }
+            Box::new(ret)
+        }
+        args::OutputType::Biblatex => {
+            let mut ret = bibwriter::BiblatexWriter::new(output).with_format(config.format.clone());
+            if config.latex_encode {
+                ret = ret.with_latex_encoding(verbatim_fields);
+            }
+            Box::new(ret)
+        }
+    }
+}
+
+/// Write a converted row. Entries that cannot be represented in the output format are rejected.
+fn write_row<W: std::io::Write>(
+    writer: &mut dyn bibwriter::BibWrite,
+    row: group::ConvertedRow,
+    rejects: &mut Option<rejects::RejectWriter<W>>,
+    summary: &mut Summary,
+    policy: &policy::ErrorPolicy,
+    report: &mut Option<report::Report>,
+) -> Result<(), Error> {
+    match writer.write(&row.entry) {
+        Ok(entry) => {
+            // the report counts the fields as they are written, e.g., "journaltitle" in BibLaTeX
+            if let Some(x) = report.as_mut() {
+                x.add_entry(&entry, &row.entry);
+            }
+            Ok(())
+        }
+        Err(e @ Error::Bibtex { .. }) => {
+            let e = e.at_line(row.line);
+            if let Some(x) = report.as_mut() {
+                x.add_skipped(row.line, &reason(&e));
+            }
+            reject(e, row.line, row.byte, &row.raw, rejects, summary, policy)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Count a row that could not be converted and write it to the rejects file. Returns an error if
+/// the error policy doesn't tolerate the row.
+fn reject<W: std::io::Write>(
+    e: Error,
+    line: u64,
+    byte: u64,
+    raw: &[String],
+    rejects: &mut Option<rejects::RejectWriter<W>>,
+    summary: &mut Summary,
+    policy: &policy::ErrorPolicy,
+) -> Result<(), Error> {
+    summary.rejected += 1;
+    if let Some(rejects) = rejects.as_mut() {
+        rejects.write(line, byte, &reason(&e), raw)?;
+    }
+    if !policy.allows_errors() {
+        return Err(e);
+    }
+    error!("Error converting item: {}. Skipping item.", e);
+    if !policy.is_tolerated(summary.rows, summary.rejected, false) {
+        return Err(Error::TooManyErrors {
+            errors: summary.rejected,
+            rows: summary.rows,
+        });
+    }
+
+    Ok(())
+}
+
+/// Reason why a row could not be converted, without its position
+fn reason(e: &Error) -> String {
+    match e {
+        Error::Csv { reason, .. } => reason.clone(),
+        Error::Bibtex { key, message, .. } => format!("Entry \"{}\": {}", key, message),
+        x => x.to_string(),
+    }
+}
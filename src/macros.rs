@@ -0,0 +1,308 @@
+use crate::dialect;
+use crate::Error;
+
+/// Words that are skipped when a macro name is built from a value
+const STOP_WORDS: [&str; 10] = [
+    "a", "an", "and", "for", "in", "of", "on", "the", "to", "und",
+];
+
+/// String macros that are defined once by `@string{name = {value}}` and referenced by their name
+/// instead of repeating the value in every entry, e.g., `journal = jacm`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StringMacros {
+    // Fields whose values are replaced by macros
+    fields: Vec<String>,
+    // Definitions as name and value in the order they are written
+    definitions: Vec<(String, String)>,
+    // Names of the macros by their value
+    names: std::collections::HashMap<String, String>,
+    // Use the predefined month macros like "jan" in the month field
+    months: bool,
+}
+
+impl StringMacros {
+    /// Create macros for the given fields. The field names of both BibTeX and BibLaTeX are
+    /// used, e.g., "journaltitle" for "journal".
+    pub fn new(fields: &[String]) -> Self {
+        let mut ret = Self::default();
+        for field in fields.iter().map(|x| x.trim().to_lowercase()) {
+            if let Some(x) = dialect::counterpart(&field) {
+                ret.fields.push(String::from(x));
+            }
+            ret.fields.push(field);
+        }
+        ret
+    }
+
+    /// Write months as predefined macros like `mar` instead of `{March}`
+    pub fn with_months(mut self) -> Self {
+        self.months = true;
+        self
+    }
+
+    /// Read definitions from a file. Every line contains one definition, either as
+    /// `@string{name = {value}}` or simply `name = value`. Values are plain text like in the CSV
+    /// file. Empty lines and lines starting with `%` are ignored.
+    pub fn with_file(mut self, path: &std::path::Path) -> Result<Self, Error> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            Error::io(
+                format!("Could not read string macros: {}", path.display()),
+                e,
+            )
+        })?;
+        let string = regex::Regex::new("(?i)^@string\\s*[{(](.*)[})]$").unwrap();
+
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('%') {
+                continue;
+            }
+            let definition = match string.captures(line) {
+                Some(x) => x.get(1).unwrap().as_str(),
+                None => line,
+            };
+            let (name, value) = definition
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), strip_delimiters(v.trim())))
+                .filter(|(k, v)| is_valid_name(k) && !v.is_empty())
+                .ok_or_else(|| {
+                    Error::Config(format!(
+                        "Invalid string macro in line {} of \"{}\": {}",
+                        i + 1,
+                        path.display(),
+                        line
+                    ))
+                })?;
+            self.define(String::from(name), String::from(value));
+        }
+
+        Ok(self)
+    }
+
+    /// Define macros for all values that occur at least `min_count` times in the fields of the
+    /// entries. The names are built from the initials of the words, e.g., `jacm` for "Journal
+    /// of the ACM".
+    pub fn add_frequent<'a>(
+        &mut self,
+        entries: impl IntoIterator<Item = &'a biblatex::Entry>,
+        min_count: usize,
+    ) {
+        let mut counts = std::collections::BTreeMap::new();
+        for entry in entries {
+            for field in &self.fields {
+                if let Some(x) = entry.get(field).and_then(plain_value) {
+                    *counts.entry(x).or_insert(0) += 1;
+                }
+            }
+        }
+
+        for (value, count) in counts {
+            if count < min_count || self.names.contains_key(&value) {
+                continue;
+            }
+            // values without letters or digits get no macro
+            if let Some(x) = build_name(&value) {
+                let name = unique_name(self, &x);
+                self.define(name, value);
+            }
+        }
+    }
+
+    /// Are there any macros to use?
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty() && !self.months
+    }
+
+    /// Definitions as name and value
+    pub fn definitions(&self) -> &[(String, String)] {
+        &self.definitions
+    }
+
+    /// Name of the macro to write instead of the value of a field
+    pub fn lookup(&self, field: &str, value: biblatex::ChunksRef) -> Option<String> {
+        let value = plain_value(value)?;
+        if self.months && field == "month" {
+            return dialect::month_macro(&value);
+        }
+        if !self.fields.iter().any(|x| x == field) {
+            return None;
+        }
+        self.names.get(&value).cloned()
+    }
+
+    fn define(&mut self, name: String, value: String) {
+        self.names.insert(value.clone(), name.clone());
+        self.definitions.push((name, value));
+    }
+}
+
+/// Value of a field if it consists of normal text only
+fn plain_value(value: biblatex::ChunksRef) -> Option<String> {
+    let mut ret = String::new();
+    for chunk in value {
+        match &chunk.v {
+            biblatex::Chunk::Normal(x) => ret.push_str(x),
+            _ => return None,
+        }
+    }
+    Some(String::from(ret.trim())).filter(|x| !x.is_empty())
+}
+
+/// Remove the braces or quotes around a value
+fn strip_delimiters(value: &str) -> &str {
+    value
+        .strip_prefix('{')
+        .and_then(|x| x.strip_suffix('}'))
+        .or_else(|| value.strip_prefix('"').and_then(|x| x.strip_suffix('"')))
+        .unwrap_or(value)
+        .trim()
+}
+
+/// Can the name be used as a macro? It has to start with a letter.
+fn is_valid_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-:.+/".contains(c))
+}
+
+/// Build a macro name from the initials of the words of a value, or from the only word.
+/// Acronyms like "ACM" are kept as they are. Stop words are left out, unless the value consists
+/// of stop words only. Returns `None` if the value has no letters or digits.
+fn build_name(value: &str) -> Option<String> {
+    let all_words: Vec<&str> = value
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .collect();
+    let mut words: Vec<&str> = all_words
+        .iter()
+        .filter(|x| !STOP_WORDS.contains(&x.to_lowercase().as_str()))
+        .copied()
+        .collect();
+    if words.is_empty() {
+        words = all_words;
+    }
+    let ret: String = match words.as_slice() {
+        [word] => word.to_lowercase(),
+        _ => words
+            .iter()
+            .map(|x| {
+                if x.chars().count() > 1 && x.chars().all(|c| !c.is_lowercase()) {
+                    x.to_lowercase()
+                } else {
+                    x.chars().take(1).collect::<String>().to_lowercase()
+                }
+            })
+            .collect(),
+    };
+    let ret: String = ret.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    if ret.is_empty() {
+        None
+    } else if ret.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        Some(ret)
+    } else {
+        Some(format!("s{}", ret))
+    }
+}
+
+/// Append "_2", "_3", ... if the name is used already, including the month macros
+fn unique_name(macros: &StringMacros, name: &str) -> String {
+    let is_used = |x: &str| {
+        macros.definitions.iter().any(|(n, _)| n == x)
+            || dialect::month_macro(x).as_deref() == Some(x)
+    };
+    let mut ret = String::from(name);
+    let mut counter = 1;
+    while is_used(&ret) {
+        counter += 1;
+        ret = format!("{}_{}", name, counter);
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(journal: &str) -> biblatex::Entry {
+        let mut ret = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        ret.set(
+            "journal",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from(journal),
+            ))],
+        );
+        ret
+    }
+
+    #[test]
+    fn test_add_frequent() {
+        let entries = vec![
+            entry("Journal of the ACM"),
+            entry("Nature"),
+            entry("Journal of the ACM"),
+            entry("Journal of the ACM"),
+            entry("Nature"),
+            entry("Journal of Applied Computational Mathematics"),
+            entry("Journal of Applied Computational Mathematics"),
+            entry("Rare Journal"),
+        ];
+        let mut macros = StringMacros::new(&[String::from("journal")]);
+        macros.add_frequent(&entries, 2);
+
+        assert_eq!(
+            macros.definitions(),
+            &[
+                (
+                    String::from("jacm"),
+                    String::from("Journal of Applied Computational Mathematics")
+                ),
+                (String::from("jacm_2"), String::from("Journal of the ACM")),
+                (String::from("nature"), String::from("Nature")),
+            ]
+        );
+        assert_eq!(
+            macros.lookup("journaltitle", entries[1].get("journal").unwrap()),
+            Some(String::from("nature"))
+        );
+        assert_eq!(
+            macros.lookup("title", entries[1].get("journal").unwrap()),
+            None
+        );
+        assert_eq!(
+            macros.lookup("journal", entries[7].get("journal").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_months() {
+        let macros = StringMacros::new(&[]).with_months();
+        let month = vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+            String::from("March"),
+        ))];
+        assert_eq!(macros.lookup("month", &month), Some(String::from("mar")));
+        assert_eq!(macros.lookup("note", &month), None);
+        assert!(!macros.is_empty());
+        assert!(StringMacros::new(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_build_name() {
+        let name = |x| build_name(x).unwrap();
+        assert_eq!(name("Journal of the ACM"), "jacm");
+        assert_eq!(name("Physical Review Letters"), "prl");
+        assert_eq!(name("Nature"), "nature");
+        assert_eq!(name("3D Research"), "s3dr");
+        assert_eq!(name("IEEE Transactions on Software Engineering"), "ieeetse");
+        // values of stop words only
+        assert_eq!(name("The"), "the");
+        assert_eq!(name("A"), "a");
+        assert_eq!(name("Of The"), "ot");
+        assert_eq!(build_name("--"), None);
+        assert_eq!(build_name("日本"), None);
+        assert_eq!(strip_delimiters("{Nature}"), "Nature");
+        assert_eq!(strip_delimiters("\"Nature\""), "Nature");
+        assert!(!is_valid_name("3d"));
+    }
+}
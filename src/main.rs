@@ -1,11 +1,12 @@
 use csv2bibtex::args;
+use csv2bibtex::Error;
 use log::error;
 
 fn main() {
     // build config structure
     let config = args::Config::new().unwrap_or_else(|e| {
         eprintln!("Problem parsing arguments: {}.", e);
-        std::process::exit(1);
+        std::process::exit(e.exit_code());
     });
 
     // initialize logger
@@ -17,9 +18,23 @@ fn main() {
     )
     .unwrap();
 
-    // run main function
-    if let Err(e) = csv2bibtex::run(&config) {
-        error!("{:#}.", e);
-        std::process::exit(1);
+    // run main function, or only inspect the input files
+    let result = if config.inspect {
+        csv2bibtex::inspect::run(&config, &mut std::io::stdout())
+    } else if config.print_profile {
+        csv2bibtex::suggest::run(&config, &mut std::io::stdout())
+    } else if config.wizard {
+        csv2bibtex::wizard::run(&config, std::io::stdin().lock(), std::io::stdout())
+    } else {
+        csv2bibtex::run(&config).map(|_| ())
+    };
+    if let Err(e) = result {
+        match e {
+            Error::Csv { .. } | Error::Bibtex { .. } => {
+                error!("{}. Option \"-l\" might help.", e)
+            }
+            _ => error!("{}.", e),
+        }
+        std::process::exit(e.exit_code());
     }
 }
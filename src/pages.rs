@@ -0,0 +1,86 @@
+/// Characters that separate the first and the last page of a range
+const DASHES: [char; 7] = ['-', '‐', '‑', '‒', '–', '—', '−'];
+
+/// Prefixes of page numbers that are removed, in lowercase
+const PREFIXES: [&str; 5] = ["pages", "pp.", "pp", "p.", "s."];
+
+/// Normalize page ranges to the form `45--67`. Prefixes like "pp." are removed, abbreviated last
+/// pages like in `1234-45` are expanded, and incomplete ranges like `45--` are reduced to a single
+/// page. Several ranges are separated by ", ". Article numbers like `e0123` are kept as they are.
+/// Pages that cannot be recognized are returned as they are.
+pub fn normalize(input: &str) -> String {
+    let input = input.trim();
+    let ranges: Vec<String> = input
+        .split([',', ';'])
+        .map(normalize_range)
+        .filter(|x| !x.is_empty())
+        .collect();
+    if ranges.iter().any(|x| x.contains(char::is_whitespace)) {
+        return String::from(input);
+    }
+    ranges.join(", ")
+}
+
+/// Normalize a single page range
+fn normalize_range(input: &str) -> String {
+    let mut input = input.trim();
+    for prefix in PREFIXES {
+        if input
+            .get(..prefix.len())
+            .is_some_and(|x| x.eq_ignore_ascii_case(prefix))
+        {
+            input = input[prefix.len()..].trim_start();
+            break;
+        }
+    }
+
+    let pages: Vec<&str> = input
+        .split(DASHES)
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty())
+        .collect();
+    match pages.as_slice() {
+        [] => String::new(),
+        [page] => String::from(*page),
+        [first, last] if first == last => String::from(*first),
+        [first, last] => format!("{}--{}", first, expand_last_page(first, last)),
+        _ => String::from(input),
+    }
+}
+
+/// Expand an abbreviated last page like "45" in "1234-45" to "1245". The last page is returned
+/// as it is if it isn't abbreviated.
+fn expand_last_page<'a>(first: &str, last: &'a str) -> std::borrow::Cow<'a, str> {
+    let is_number = |x: &str| x.chars().all(|c| c.is_ascii_digit());
+    if !is_number(first) || !is_number(last) || last.len() >= first.len() {
+        return std::borrow::Cow::Borrowed(last);
+    }
+
+    let expanded = format!("{}{}", &first[..first.len() - last.len()], last);
+    match (first.parse::<u64>(), expanded.parse::<u64>()) {
+        (Ok(x), Ok(y)) if y > x => std::borrow::Cow::Owned(expanded),
+        _ => std::borrow::Cow::Borrowed(last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("45-67"), "45--67");
+        assert_eq!(normalize("45–67"), "45--67");
+        assert_eq!(normalize("pp. 45 - 67"), "45--67");
+        assert_eq!(normalize("45--67"), "45--67");
+        assert_eq!(normalize("1234-45"), "1234--1245");
+        assert_eq!(normalize("1299-5"), "1299--5");
+        assert_eq!(normalize("45--"), "45");
+        assert_eq!(normalize("--"), "");
+        assert_eq!(normalize("45-45"), "45");
+        assert_eq!(normalize("e0123"), "e0123");
+        assert_eq!(normalize("S. 10-12, 15"), "10--12, 15");
+        assert_eq!(normalize("xii-xv"), "xii--xv");
+        assert_eq!(normalize("see chapter 2"), "see chapter 2");
+    }
+}
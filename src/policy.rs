@@ -0,0 +1,94 @@
+/// Minimum number of rows before the error rate is checked during the conversion. Before, only
+/// the absolute number of errors is checked.
+const MIN_ROWS_FOR_RATE: usize = 20;
+
+/// Decides how many rows may fail before the conversion is aborted
+#[derive(Debug, Clone, Default)]
+pub struct ErrorPolicy {
+    // Skip every row that fails
+    lazy: bool,
+    // Maximum number of rows that may fail
+    max_errors: Option<usize>,
+    // Maximum fraction of rows that may fail
+    max_error_rate: Option<f64>,
+}
+
+impl ErrorPolicy {
+    pub fn new(lazy: bool, max_errors: Option<usize>, max_error_rate: Option<f64>) -> Self {
+        Self {
+            lazy,
+            max_errors,
+            max_error_rate,
+        }
+    }
+
+    /// May rows fail at all?
+    pub fn allows_errors(&self) -> bool {
+        self.lazy || self.max_errors.is_some() || self.max_error_rate.is_some()
+    }
+
+    /// Check whether `errors` failed rows out of `rows` read rows are still tolerated. If
+    /// `finished` is set, all rows have been read and the error rate is checked regardless of the
+    /// number of rows.
+    pub fn is_tolerated(&self, rows: usize, errors: usize, finished: bool) -> bool {
+        if !self.allows_errors() {
+            return errors == 0;
+        }
+        if let Some(max) = self.max_errors {
+            if errors > max {
+                return false;
+            }
+        }
+        if let Some(max) = self.max_error_rate {
+            if rows > 0 && (finished || rows >= MIN_ROWS_FOR_RATE) {
+                return errors as f64 / rows as f64 <= max;
+            }
+        }
+
+        true
+    }
+}
+
+/// Parse an error rate given either as percentage ("5%") or as fraction ("0.05")
+pub fn parse_rate(input: &str) -> Option<f64> {
+    let input = input.trim();
+    let rate = match input.strip_suffix('%') {
+        Some(x) => x.trim().parse::<f64>().ok()? / 100.0,
+        None => input.parse::<f64>().ok()?,
+    };
+
+    (0.0..=1.0).contains(&rate).then_some(rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy() {
+        let policy = ErrorPolicy::new(false, None, None);
+        assert!(policy.is_tolerated(10, 0, false));
+        assert!(!policy.is_tolerated(10, 1, false));
+
+        let policy = ErrorPolicy::new(true, None, None);
+        assert!(policy.is_tolerated(10, 10, true));
+
+        let policy = ErrorPolicy::new(false, Some(2), None);
+        assert!(policy.is_tolerated(10, 2, false));
+        assert!(!policy.is_tolerated(10, 3, false));
+
+        let policy = ErrorPolicy::new(false, None, Some(0.05));
+        assert!(policy.is_tolerated(10, 5, false));
+        assert!(!policy.is_tolerated(10, 5, true));
+        assert!(!policy.is_tolerated(20, 2, false));
+        assert!(policy.is_tolerated(100, 5, true));
+    }
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("5%"), Some(0.05));
+        assert_eq!(parse_rate("0.1"), Some(0.1));
+        assert_eq!(parse_rate("150%"), None);
+        assert_eq!(parse_rate("abc"), None);
+    }
+}
@@ -0,0 +1,179 @@
+use crate::converter;
+use crate::Error;
+
+/// Field mapping that is saved in a file to reuse it for similar CSV files. Every line assigns a
+/// template to a field like `--field-mapping`, e.g., `author = [[Authors]]`. Lines starting with
+/// `#` are comments.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Profile {
+    /// Fields and their templates in the order of the file
+    pub mapping: Vec<(String, String)>,
+    // Comments written at the beginning of the file
+    comments: Vec<String>,
+}
+
+impl Profile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a profile from a file
+    pub fn from_file(path: &std::path::Path) -> Result<Self, Error> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            Error::io(
+                format!("Could not read mapping profile: {}", path.display()),
+                e,
+            )
+        })?;
+        Self::parse(&content, &path.display().to_string())
+    }
+
+    /// Read a profile, see `from_file`
+    fn parse(content: &str, name: &str) -> Result<Self, Error> {
+        let mut ret = Self::new();
+        for (i, line) in content.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            // split at the first "=" only, conditions may contain "==" or "!="
+            let (field, template) = line
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .filter(|(k, v)| !k.is_empty() && !v.is_empty())
+                .ok_or_else(|| {
+                    Error::Config(format!(
+                        "Invalid field mapping in line {} of \"{}\": {}",
+                        i + 1,
+                        name,
+                        line
+                    ))
+                })?;
+            converter::check_template(template)?;
+            ret.add(field, template);
+        }
+        Ok(ret)
+    }
+
+    /// Add a comment that is written at the beginning of the file
+    pub fn with_comment(mut self, comment: &str) -> Self {
+        self.comments.push(String::from(comment));
+        self
+    }
+
+    /// Assign a template to a field. A field that is in the profile already is replaced.
+    pub fn add(&mut self, field: &str, template: &str) {
+        match self.mapping.iter_mut().find(|(x, _)| x == field) {
+            Some(x) => x.1 = String::from(template),
+            None => self
+                .mapping
+                .push((String::from(field), String::from(template))),
+        }
+    }
+
+    /// Add the mapping to fields that are not mapped yet, e.g., by `--field-mapping`
+    pub fn apply(&self, mapping: &mut std::collections::HashMap<String, String>) {
+        for (field, template) in &self.mapping {
+            mapping
+                .entry(field.clone())
+                .or_insert_with(|| template.clone());
+        }
+    }
+
+    /// Write the profile. Columns of the header that are not used by any template are listed as
+    /// commented out mappings to fields of the same name, ready to be edited.
+    pub fn write<W: std::io::Write>(&self, out: &mut W, headers: &[String]) -> std::io::Result<()> {
+        writeln!(
+            out,
+            "# csv2bibtex mapping profile, use it with --profile FILE"
+        )?;
+        writeln!(
+            out,
+            "# One field per line like --field-mapping: FIELD = TEMPLATE"
+        )?;
+        for comment in &self.comments {
+            writeln!(out, "# {}", comment)?;
+        }
+        writeln!(out)?;
+        for (field, template) in &self.mapping {
+            writeln!(out, "{} = {}", field, template)?;
+        }
+
+        let unused: Vec<&String> = headers
+            .iter()
+            .filter(|x| {
+                !self
+                    .mapping
+                    .iter()
+                    .any(|(_, template)| converter::template_columns(template).contains(x))
+            })
+            .collect();
+        if !unused.is_empty() {
+            writeln!(out)?;
+            writeln!(out, "# Columns without field")?;
+        }
+        for column in unused {
+            writeln!(out, "# {} = [[{}]]", converter::field_name(column), column)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile() {
+        let profile = Profile::parse(
+            "# comment\n\nauthor = [[Authors]]\nentrytype=article if [[DT]]==Article\n",
+            "test.profile",
+        )
+        .unwrap();
+        assert_eq!(
+            profile.mapping,
+            vec![
+                (String::from("author"), String::from("[[Authors]]")),
+                (
+                    String::from("entrytype"),
+                    String::from("article if [[DT]]==Article")
+                ),
+            ]
+        );
+        assert!(Profile::parse("author [[Authors]]", "test.profile").is_err());
+
+        // mappings given on the command line take precedence
+        let mut mapping = std::collections::HashMap::new();
+        mapping.insert(String::from("author"), String::from("[[AU]]"));
+        profile.apply(&mut mapping);
+        assert_eq!(mapping["author"], "[[AU]]");
+        assert_eq!(mapping["entrytype"], "article if [[DT]]==Article");
+
+        let mut out = Vec::new();
+        profile
+            .with_comment("Generated for \"test.csv\"")
+            .write(
+                &mut out,
+                &[
+                    String::from("Authors"),
+                    String::from("DT"),
+                    String::from("Funding Info"),
+                ],
+            )
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
+# csv2bibtex mapping profile, use it with --profile FILE
+# One field per line like --field-mapping: FIELD = TEMPLATE
+# Generated for \"test.csv\"
+
+author = [[Authors]]
+entrytype = article if [[DT]]==Article
+
+# Columns without field
+# funding_info = [[Funding Info]]
+"
+        );
+    }
+}
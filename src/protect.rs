@@ -0,0 +1,144 @@
+/// Fields whose case is changed by BibTeX styles
+const TITLE_FIELDS: [&str; 11] = [
+    "title",
+    "subtitle",
+    "titleaddon",
+    "shorttitle",
+    "booktitle",
+    "booksubtitle",
+    "maintitle",
+    "mainsubtitle",
+    "eventtitle",
+    "issuetitle",
+    "origtitle",
+];
+
+/// Protect words in titles against changes of their case
+pub struct TitleProtector {
+    // Words that are always protected, e.g., proper nouns like "Bayesian"
+    words: Vec<String>,
+    // Regex that splits a title into words
+    regex: regex::Regex,
+}
+
+impl TitleProtector {
+    pub fn new(words: &[String]) -> Self {
+        Self {
+            words: words.to_vec(),
+            regex: regex::Regex::new("[[:alnum:]][[:alnum:]'’-]*").unwrap(),
+        }
+    }
+
+    /// Is the field a title-like field?
+    pub fn is_title_field(field: &str) -> bool {
+        TITLE_FIELDS.contains(&field)
+    }
+
+    /// Split a title into chunks. Acronyms (e.g., "DNA" or "COVID-19"), words in camel case
+    /// (e.g., "iPhone"), and words of the user-supplied list are returned as verbatim chunks.
+    /// Everything else is returned as normal chunks.
+    pub fn protect(&self, title: &str) -> biblatex::Chunks {
+        let mut ret = Vec::new();
+        let mut last = 0;
+
+        for word in self.regex.find_iter(title) {
+            if !self.is_protected(word.as_str()) {
+                continue;
+            }
+            if word.start() > last {
+                ret.push(biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                    String::from(&title[last..word.start()]),
+                )));
+            }
+            ret.push(biblatex::Spanned::detached(biblatex::Chunk::Verbatim(
+                String::from(word.as_str()),
+            )));
+            last = word.end();
+        }
+
+        if last < title.len() || ret.is_empty() {
+            ret.push(biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from(&title[last..]),
+            )));
+        }
+
+        ret
+    }
+
+    /// Should the word be protected?
+    fn is_protected(&self, word: &str) -> bool {
+        if self.words.iter().any(|x| x == word) {
+            return true;
+        }
+
+        let letters: Vec<char> = word.chars().filter(|c| c.is_alphabetic()).collect();
+        // acronyms: at least two letters, all of them uppercase
+        if letters.len() >= 2 && letters.iter().all(|c| c.is_uppercase()) {
+            return true;
+        }
+        // camel case: an uppercase letter after the first letter of a part, so that hyphenated
+        // words in title case like "Data-Driven" are not protected
+        word.split('-').any(|part| {
+            part.chars()
+                .filter(|c| c.is_alphabetic())
+                .skip(1)
+                .any(|c| c.is_uppercase())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normal(value: &str) -> biblatex::Spanned<biblatex::Chunk> {
+        biblatex::Spanned::detached(biblatex::Chunk::Normal(String::from(value)))
+    }
+
+    fn verbatim(value: &str) -> biblatex::Spanned<biblatex::Chunk> {
+        biblatex::Spanned::detached(biblatex::Chunk::Verbatim(String::from(value)))
+    }
+
+    #[test]
+    fn test_protect() {
+        let protector = TitleProtector::new(&[String::from("Bayesian")]);
+
+        assert_eq!(
+            protector.protect("DNA sequencing and COVID-19: A Bayesian view on iPhone data"),
+            vec![
+                verbatim("DNA"),
+                normal(" sequencing and "),
+                verbatim("COVID-19"),
+                normal(": A "),
+                verbatim("Bayesian"),
+                normal(" view on "),
+                verbatim("iPhone"),
+                normal(" data"),
+            ]
+        );
+        assert_eq!(
+            protector.protect("My eloquent title"),
+            vec![normal("My eloquent title")]
+        );
+        assert_eq!(protector.protect("NASA"), vec![verbatim("NASA")]);
+    }
+
+    #[test]
+    fn test_protect_hyphenated() {
+        let protector = TitleProtector::new(&[]);
+
+        assert_eq!(
+            protector.protect("Data-Driven Methods for Self-Driving Cars"),
+            vec![normal("Data-Driven Methods for Self-Driving Cars")]
+        );
+        assert_eq!(
+            protector.protect("Pre-iPhone and SARS-CoV-2 Studies"),
+            vec![
+                verbatim("Pre-iPhone"),
+                normal(" and "),
+                verbatim("SARS-CoV-2"),
+                normal(" Studies"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use crate::Error;
+
+/// Writer for rows that could not be converted. Rejected rows are written as CSV file with the
+/// columns `line`, `byte`, and `error`, followed by the original cells of the row. If rows of
+/// several input files are written, the column `file` is added in front.
+pub struct RejectWriter<W: std::io::Write> {
+    writer: csv::Writer<W>,
+    counter: usize,
+    // Input file of the following rows. If `None`, there is no `file` column.
+    source: Option<String>,
+}
+
+impl<W: std::io::Write> RejectWriter<W> {
+    pub fn new(
+        writer: W,
+        headers: &[String],
+        delimiter: &str,
+        source_column: bool,
+    ) -> Result<Self, Error> {
+        let delimiter = if delimiter == "\\t" { "\t" } else { delimiter };
+
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(delimiter.as_bytes()[0])
+            .flexible(true)
+            .from_writer(writer);
+        let columns: &[&str] = if source_column {
+            &["file", "line", "byte", "error"]
+        } else {
+            &["line", "byte", "error"]
+        };
+        writer
+            .write_record(
+                columns
+                    .iter()
+                    .copied()
+                    .chain(headers.iter().map(|x| x.as_str())),
+            )
+            .map_err(|e| Error::io(String::from("Could not write rejected rows"), e))?;
+
+        Ok(Self {
+            writer,
+            counter: 0,
+            source: source_column.then(String::new),
+        })
+    }
+
+    /// Set the input file of the following rows. Ignored if there is no `file` column.
+    pub fn set_source(&mut self, source: &str) {
+        if let Some(x) = self.source.as_mut() {
+            *x = String::from(source);
+        }
+    }
+
+    /// Write a rejected row together with its position and the reason for rejecting it
+    pub fn write(
+        &mut self,
+        line: u64,
+        byte: u64,
+        reason: &str,
+        raw: &[String],
+    ) -> Result<(), Error> {
+        let (line, byte) = (line.to_string(), byte.to_string());
+        self.writer
+            .write_record(
+                self.source
+                    .iter()
+                    .map(|x| x.as_str())
+                    .chain([line.as_str(), byte.as_str(), reason])
+                    .chain(raw.iter().map(|x| x.as_str())),
+            )
+            .map_err(|e| Error::io(String::from("Could not write rejected rows"), e))?;
+        self.counter += 1;
+
+        Ok(())
+    }
+
+    pub fn get_num_written_rows(&self) -> usize {
+        self.counter
+    }
+
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.writer
+            .flush()
+            .map_err(|e| Error::io(String::from("Could not write rejected rows"), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejects() {
+        let headers = vec![String::from("author"), String::from("title")];
+        let mut writer = RejectWriter::new(Vec::new(), &headers, ";", false).unwrap();
+        writer
+            .write(3, 42, "Something; failed", &[String::from("alice")])
+            .unwrap();
+
+        assert_eq!(writer.get_num_written_rows(), 1);
+        assert_eq!(
+            String::from_utf8(writer.writer.into_inner().unwrap()).unwrap(),
+            "line;byte;error;author;title\n3;42;\"Something; failed\";alice\n"
+        );
+    }
+
+    #[test]
+    fn test_rejects_with_source() {
+        let headers = vec![String::from("author")];
+        let mut writer = RejectWriter::new(Vec::new(), &headers, ",", true).unwrap();
+        writer.set_source("a.csv");
+        writer
+            .write(3, 42, "failed", &[String::from("alice")])
+            .unwrap();
+        writer.set_source("b.csv");
+        writer
+            .write(2, 10, "failed", &[String::from("bob")])
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.writer.into_inner().unwrap()).unwrap(),
+            "file,line,byte,error,author\na.csv,3,42,failed,alice\nb.csv,2,10,failed,bob\n"
+        );
+    }
+}
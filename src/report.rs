@@ -0,0 +1,367 @@
+use crate::Summary;
+
+/// Fields of the mapping that are no fields of the entries
+const SPECIAL_FIELDS: [&str; 2] = ["entrytype", "bibtexkey"];
+
+/// Format of the report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// Plain text for humans
+    #[default]
+    Text,
+    /// JSON for further processing
+    Json,
+}
+
+/// Statistics on the quality of a conversion, e.g., how many entries have an author
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    // Number of rows, entries, and skipped rows
+    summary: Summary,
+    // Number of written entries, counted by the report itself
+    entries: usize,
+    // Number of entries by their type
+    entry_types: std::collections::BTreeMap<String, usize>,
+    // Number of entries by the fields they have
+    fields: std::collections::BTreeMap<String, usize>,
+    // Fields of the entries before the writer converted them, e.g., "journal" instead of
+    // "journaltitle", to find mapped fields without values
+    converted_fields: std::collections::BTreeSet<String>,
+    // Fields of the field mapping
+    mapped_fields: Vec<String>,
+    // Line and reason of every skipped row
+    skipped: Vec<(u64, String)>,
+    // Original and new keys of renamed entries
+    renamed_keys: Vec<(String, String)>,
+}
+
+impl Report {
+    /// Create a new report. `mapped_fields` are the fields of the field mapping, fields that
+    /// never get a value are reported.
+    pub fn new(mapped_fields: &[String]) -> Self {
+        let mut mapped_fields: Vec<String> = mapped_fields
+            .iter()
+            .filter(|x| !SPECIAL_FIELDS.contains(&x.as_str()))
+            .cloned()
+            .collect();
+        mapped_fields.sort();
+        Self {
+            mapped_fields,
+            ..Default::default()
+        }
+    }
+
+    /// Count a written entry. `written` is the entry as the writer wrote it, `converted` the
+    /// entry as it was converted from the row.
+    pub fn add_entry(&mut self, written: &biblatex::Entry, converted: &biblatex::Entry) {
+        self.entries += 1;
+        *self
+            .entry_types
+            .entry(written.entry_type.to_string())
+            .or_default() += 1;
+        for field in written.fields.keys() {
+            *self.fields.entry(field.clone()).or_default() += 1;
+        }
+        self.converted_fields
+            .extend(converted.fields.keys().cloned());
+    }
+
+    /// Add a row that could not be converted
+    pub fn add_skipped(&mut self, line: u64, reason: &str) {
+        self.skipped.push((line, String::from(reason)));
+    }
+
+    /// Set the numbers of rows and entries of the finished run
+    pub fn set_summary(&mut self, summary: &Summary) {
+        self.summary = summary.clone();
+    }
+
+    /// Set the keys that were renamed because they existed already
+    pub fn set_renamed_keys(&mut self, keys: Vec<(String, String)>) {
+        self.renamed_keys = keys;
+    }
+
+    /// Fields of the mapping that no entry has
+    pub fn empty_mappings(&self) -> Vec<&str> {
+        self.mapped_fields
+            .iter()
+            .filter(|x| !self.converted_fields.contains(*x))
+            .map(|x| x.as_str())
+            .collect()
+    }
+
+    /// Fraction of the entries that have a field in percent
+    fn fill_rate(&self, count: usize) -> f64 {
+        match self.entries {
+            0 => 0.0,
+            x => count as f64 * 100.0 / x as f64,
+        }
+    }
+
+    /// Write the report as text or JSON
+    pub fn write<W: std::io::Write>(
+        &self,
+        out: &mut W,
+        format: ReportFormat,
+    ) -> std::io::Result<()> {
+        match format {
+            ReportFormat::Text => self.write_text(out),
+            ReportFormat::Json => self.write_json(out),
+        }
+    }
+
+    fn write_text<W: std::io::Write>(&self, out: &mut W) -> std::io::Result<()> {
+        writeln!(out, "Conversion report")?;
+        writeln!(out)?;
+        writeln!(out, "Rows:          {}", self.summary.rows)?;
+        writeln!(out, "Entries:       {}", self.summary.entries)?;
+        writeln!(out, "Skipped rows:  {}", self.summary.rejected)?;
+        writeln!(out, "Filtered rows: {}", self.summary.filtered)?;
+
+        let width = |keys: &mut dyn Iterator<Item = &String>| {
+            keys.map(|x| x.chars().count()).max().unwrap_or_default()
+        };
+
+        writeln!(out)?;
+        writeln!(out, "Entries per type:")?;
+        let type_width = width(&mut self.entry_types.keys());
+        for (entry_type, count) in &self.entry_types {
+            writeln!(out, "    {:type_width$}  {:>6}", entry_type, count)?;
+        }
+
+        writeln!(out)?;
+        writeln!(out, "Fill rate per field:")?;
+        let field_width = width(&mut self.fields.keys());
+        for (field, count) in &self.fields {
+            writeln!(
+                out,
+                "    {:field_width$}  {:>6}  {:>5.1}%",
+                field,
+                count,
+                self.fill_rate(*count)
+            )?;
+        }
+
+        writeln!(out)?;
+        writeln!(out, "Skipped rows:")?;
+        if self.skipped.is_empty() {
+            writeln!(out, "    none")?;
+        }
+        for (line, reason) in &self.skipped {
+            writeln!(out, "    Line {}: {}", line, reason)?;
+        }
+
+        writeln!(out)?;
+        writeln!(out, "Renamed duplicate keys:")?;
+        if self.renamed_keys.is_empty() {
+            writeln!(out, "    none")?;
+        }
+        for (key, renamed) in &self.renamed_keys {
+            writeln!(out, "    {} -> {}", key, renamed)?;
+        }
+
+        writeln!(out)?;
+        writeln!(out, "Mapped fields without values:")?;
+        let empty = self.empty_mappings();
+        if empty.is_empty() {
+            writeln!(out, "    none")?;
+        }
+        for field in empty {
+            writeln!(out, "    {}", field)?;
+        }
+        Ok(())
+    }
+
+    fn write_json<W: std::io::Write>(&self, out: &mut W) -> std::io::Result<()> {
+        writeln!(out, "{{")?;
+        writeln!(out, "  \"rows\": {},", self.summary.rows)?;
+        writeln!(out, "  \"entries\": {},", self.summary.entries)?;
+        writeln!(out, "  \"skipped\": {},", self.summary.rejected)?;
+        writeln!(out, "  \"filtered\": {},", self.summary.filtered)?;
+
+        let entry_types: Vec<String> = self
+            .entry_types
+            .iter()
+            .map(|(entry_type, count)| format!("{}: {}", json_string(entry_type), count))
+            .collect();
+        writeln!(out, "  \"entry_types\": {},", json_object(&entry_types))?;
+
+        let fields: Vec<String> = self
+            .fields
+            .iter()
+            .map(|(field, count)| {
+                format!(
+                    "{}: {{\"entries\": {}, \"fill_rate\": {:.1}}}",
+                    json_string(field),
+                    count,
+                    self.fill_rate(*count)
+                )
+            })
+            .collect();
+        writeln!(out, "  \"fields\": {},", json_object(&fields))?;
+
+        let skipped: Vec<String> = self
+            .skipped
+            .iter()
+            .map(|(line, reason)| {
+                format!(
+                    "{{\"line\": {}, \"reason\": {}}}",
+                    line,
+                    json_string(reason)
+                )
+            })
+            .collect();
+        writeln!(out, "  \"skipped_rows\": {},", json_array(&skipped))?;
+
+        let renamed_keys: Vec<String> = self
+            .renamed_keys
+            .iter()
+            .map(|(key, renamed)| {
+                format!(
+                    "{{\"key\": {}, \"renamed\": {}}}",
+                    json_string(key),
+                    json_string(renamed)
+                )
+            })
+            .collect();
+        writeln!(out, "  \"renamed_keys\": {},", json_array(&renamed_keys))?;
+
+        let empty: Vec<String> = self.empty_mappings().into_iter().map(json_string).collect();
+        writeln!(out, "  \"empty_mappings\": {}", json_array(&empty))?;
+        writeln!(out, "}}")
+    }
+}
+
+/// JSON object with one member per line
+fn json_object(members: &[String]) -> String {
+    match members.is_empty() {
+        true => String::from("{}"),
+        false => format!("{{\n    {}\n  }}", members.join(",\n    ")),
+    }
+}
+
+/// JSON array with one value per line
+fn json_array(values: &[String]) -> String {
+    match values.is_empty() {
+        true => String::from("[]"),
+        false => format!("[\n    {}\n  ]", values.join(",\n    ")),
+    }
+}
+
+/// Quote and escape a string for JSON
+fn json_string(value: &str) -> String {
+    let mut ret = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            c if c.is_control() => ret.push_str(&format!("\\u{:04x}", c as u32)),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report() -> Report {
+        let mut report = Report::new(&[
+            String::from("entrytype"),
+            String::from("title"),
+            String::from("note"),
+        ]);
+        let mut entry = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        entry.set(
+            "title",
+            vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                String::from("Some Title"),
+            ))],
+        );
+        report.add_entry(&entry, &entry);
+        entry.entry_type = biblatex::EntryType::Book;
+        entry.fields.clear();
+        report.add_entry(&entry, &entry);
+        report.add_entry(&entry, &entry);
+        report.add_skipped(4, "Entry \"x\": Invalid \"date\"");
+        report.set_renamed_keys(vec![(String::from("test1"), String::from("test1_2"))]);
+        report.set_summary(&Summary {
+            rows: 4,
+            entries: 3,
+            rejected: 1,
+            filtered: 0,
+        });
+        report
+    }
+
+    #[test]
+    fn test_text() {
+        let mut out = Vec::new();
+        report().write(&mut out, ReportFormat::Text).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
+Conversion report
+
+Rows:          4
+Entries:       3
+Skipped rows:  1
+Filtered rows: 0
+
+Entries per type:
+    article       1
+    book          2
+
+Fill rate per field:
+    title       1   33.3%
+
+Skipped rows:
+    Line 4: Entry \"x\": Invalid \"date\"
+
+Renamed duplicate keys:
+    test1 -> test1_2
+
+Mapped fields without values:
+    note
+"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let mut out = Vec::new();
+        report().write(&mut out, ReportFormat::Json).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
+{
+  \"rows\": 4,
+  \"entries\": 3,
+  \"skipped\": 1,
+  \"filtered\": 0,
+  \"entry_types\": {
+    \"article\": 1,
+    \"book\": 2
+  },
+  \"fields\": {
+    \"title\": {\"entries\": 1, \"fill_rate\": 33.3}
+  },
+  \"skipped_rows\": [
+    {\"line\": 4, \"reason\": \"Entry \\\"x\\\": Invalid \\\"date\\\"\"}
+  ],
+  \"renamed_keys\": [
+    {\"key\": \"test1\", \"renamed\": \"test1_2\"}
+  ],
+  \"empty_mappings\": [
+    \"note\"
+  ]
+}
+"
+        );
+    }
+}
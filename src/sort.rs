@@ -0,0 +1,210 @@
+use crate::Error;
+
+/// A field to sort entries by
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SortKey {
+    // Field name, "key" for the key, or "entrytype" for the type of the entry
+    field: String,
+    descending: bool,
+}
+
+/// Sort entries by one or more fields, e.g., "year:desc,author". Numbers are compared as
+/// numbers and come before all other values, which are compared case-insensitive. Entries
+/// without a field come last. Ties are broken by the key of the entries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntrySorter {
+    keys: Vec<SortKey>,
+}
+
+impl EntrySorter {
+    pub fn new(input: &str) -> Result<Self, Error> {
+        let mut keys = Vec::new();
+        for item in input.split(',').map(|x| x.trim()) {
+            let (field, descending) = match item.split_once(':') {
+                Some((x, "asc")) => (x, false),
+                Some((x, "desc")) => (x, true),
+                None => (item, false),
+                _ => return Err(Error::Config(format!("Invalid sort order given: {}", item))),
+            };
+            if field.is_empty() {
+                return Err(Error::Config(format!(
+                    "Invalid sort field given: {}",
+                    input
+                )));
+            }
+            keys.push(SortKey {
+                field: field.trim().to_lowercase(),
+                descending,
+            });
+        }
+
+        Ok(Self { keys })
+    }
+
+    /// Sort the entries, keeping the order of entries with equal fields and keys
+    pub fn sort<T>(&self, items: &mut [T], entry: impl Fn(&T) -> &biblatex::Entry) {
+        items.sort_by(|a, b| self.compare(entry(a), entry(b)));
+    }
+
+    /// Compare two entries
+    pub fn compare(&self, a: &biblatex::Entry, b: &biblatex::Entry) -> std::cmp::Ordering {
+        for key in &self.keys {
+            let ordering = match (value(a, &key.field), value(b, &key.field)) {
+                (Some(x), Some(y)) => {
+                    let ordering = SortValue::new(&x).compare(&SortValue::new(&y));
+                    if key.descending {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    }
+                }
+                // missing values come last
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            };
+            if ordering.is_ne() {
+                return ordering;
+            }
+        }
+
+        a.key.cmp(&b.key)
+    }
+}
+
+/// Value of a field as it is compared: numbers first, then all other values
+#[derive(Debug, Clone, PartialEq)]
+enum SortValue {
+    Number(f64),
+    Text(String),
+}
+
+impl SortValue {
+    /// Parse a number, values like "NaN" or "inf" are compared as text
+    fn new(value: &str) -> Self {
+        match value.parse::<f64>() {
+            Ok(x) if x.is_finite() => Self::Number(x),
+            _ => Self::Text(value.to_lowercase()),
+        }
+    }
+
+    fn compare(&self, other: &Self) -> std::cmp::Ordering {
+        match (self, other) {
+            (Self::Number(x), Self::Number(y)) => x.total_cmp(y),
+            (Self::Number(_), Self::Text(_)) => std::cmp::Ordering::Less,
+            (Self::Text(_), Self::Number(_)) => std::cmp::Ordering::Greater,
+            (Self::Text(x), Self::Text(y)) => x.cmp(y),
+        }
+    }
+}
+
+/// Value of a field to sort by. If there is no year, the year of the date is used.
+fn value(entry: &biblatex::Entry, field: &str) -> Option<String> {
+    match field {
+        "key" => Some(entry.key.clone()),
+        "entrytype" => Some(entry.entry_type.to_biblatex().to_string()),
+        "year" if entry.get("year").is_none() => entry.get("date").map(|x| {
+            biblatex::ChunksExt::format_verbatim(x)
+                .chars()
+                .take(4)
+                .collect()
+        }),
+        x => entry.get(x).map(biblatex::ChunksExt::format_verbatim),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(key: &str, fields: &[(&str, &str)]) -> biblatex::Entry {
+        let mut ret = biblatex::Entry::new(String::from(key), biblatex::EntryType::Article);
+        for (k, v) in fields {
+            ret.set(
+                k,
+                vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                    String::from(*v),
+                ))],
+            );
+        }
+        ret
+    }
+
+    #[test]
+    fn test_sort() {
+        let mut entries = vec![
+            entry("c", &[("year", "2019"), ("author", "bob")]),
+            entry("b", &[("year", "2021"), ("author", "Alice")]),
+            entry("e", &[("author", "Alice")]),
+            entry("a", &[("date", "2021-03"), ("author", "Alice")]),
+            entry("d", &[("year", "2021"), ("author", "Carol")]),
+        ];
+
+        EntrySorter::new("year:desc, author")
+            .unwrap()
+            .sort(&mut entries, |x| x);
+        let keys: Vec<&str> = entries.iter().map(|x| x.key.as_str()).collect();
+        assert_eq!(keys, vec!["a", "b", "d", "c", "e"]);
+
+        EntrySorter::new("key").unwrap().sort(&mut entries, |x| x);
+        let keys: Vec<&str> = entries.iter().map(|x| x.key.as_str()).collect();
+        assert_eq!(keys, vec!["a", "b", "c", "d", "e"]);
+
+        assert!(EntrySorter::new("year:up").is_err());
+        assert!(EntrySorter::new("year,").is_err());
+    }
+
+    #[test]
+    fn test_sort_ties() {
+        // the same entries in reversed row order are sorted the same way
+        let mut entries = vec![
+            entry("b", &[("year", "2021")]),
+            entry("a", &[("year", "2021")]),
+        ];
+        let sorter = EntrySorter::new("year").unwrap();
+        sorter.sort(&mut entries, |x| x);
+        let keys: Vec<&str> = entries.iter().map(|x| x.key.as_str()).collect();
+        assert_eq!(keys, vec!["a", "b"]);
+
+        entries.reverse();
+        sorter.sort(&mut entries, |x| x);
+        let keys: Vec<&str> = entries.iter().map(|x| x.key.as_str()).collect();
+        assert_eq!(keys, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_sort_mixed_values() {
+        // numbers come first, ties are sorted by key
+        let mut entries: Vec<biblatex::Entry> = ["9", "1a", "10", "NaN", "inf", "9", "-1", "B"]
+            .iter()
+            .enumerate()
+            .map(|(i, x)| entry(&format!("entry_{}", i), &[("volume", x)]))
+            .collect();
+        EntrySorter::new("volume")
+            .unwrap()
+            .sort(&mut entries, |x| x);
+        let keys: Vec<&str> = entries.iter().map(|x| x.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "entry_6", "entry_0", "entry_5", "entry_2", "entry_1", "entry_7", "entry_4",
+                "entry_3"
+            ]
+        );
+
+        // a total order even for many mixed values
+        let mut entries: Vec<biblatex::Entry> = (0..3000)
+            .map(|i| {
+                let volume = match i % 3 {
+                    0 => format!("{}", i % 97),
+                    1 => format!("{}a", i % 89),
+                    _ => String::from("NaN"),
+                };
+                entry(&format!("entry_{}", i), &[("volume", &volume)])
+            })
+            .collect();
+        EntrySorter::new("volume")
+            .unwrap()
+            .sort(&mut entries, |x| x);
+    }
+}
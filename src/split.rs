@@ -0,0 +1,312 @@
+use crate::bibwriter::BibWrite;
+use crate::Error;
+
+/// Function that creates a writer for an output file. If the flag is set, the writer appends to a
+/// file that was written before.
+pub type CreateWriter = Box<dyn Fn(&std::path::Path, bool) -> Result<Box<dyn BibWrite>, Error>>;
+
+/// Maximum number of output files that are open at the same time
+const MAX_OPEN_FILES: usize = 64;
+
+/// Output file of one value of the field
+struct OutputFile {
+    path: std::path::PathBuf,
+    entries: usize,
+    // Writer of the file, unless it was closed to limit the number of open files
+    writer: Option<Box<dyn BibWrite>>,
+}
+
+/// Writer that routes every entry to an output file depending on the value of a field, e.g., one
+/// file per year. The output path is a template like "out/{year}.bib".
+pub struct SplitWriter {
+    // Field whose value selects the output file, or "entrytype" for the type of the entry
+    field: String,
+    // Path of the output files with a placeholder in curly braces
+    template: String,
+    // Regex to find the placeholder in `template`
+    placeholder: regex::Regex,
+    create: CreateWriter,
+    // String macros of all files
+    macros: Option<crate::macros::StringMacros>,
+    // Output files by the value of the field
+    files: std::collections::BTreeMap<String, OutputFile>,
+    // Values of the open files, the least recently used first
+    open: std::collections::VecDeque<String>,
+    max_open: usize,
+    // Keys of all written entries, so that keys are unique across all files
+    keys: std::collections::HashSet<String>,
+    // Original and new keys of renamed entries
+    renamed: Vec<(String, String)>,
+}
+
+impl SplitWriter {
+    pub fn new(field: &str, template: &str, create: CreateWriter) -> Result<Self, Error> {
+        let placeholder = regex::Regex::new("\\{[^{}]*\\}").unwrap();
+        if !placeholder.is_match(template) {
+            return Err(Error::Config(format!(
+                "Output file \"{}\" needs a placeholder like \"{{{}}}\" to split the output",
+                template, field
+            )));
+        }
+
+        Ok(Self {
+            field: String::from(field),
+            template: String::from(template),
+            placeholder,
+            create,
+            macros: None,
+            files: std::collections::BTreeMap::new(),
+            open: std::collections::VecDeque::new(),
+            max_open: MAX_OPEN_FILES,
+            keys: std::collections::HashSet::new(),
+            renamed: Vec::new(),
+        })
+    }
+
+    /// Keep at most `max_open` files open, the least recently used file is closed first
+    pub fn with_max_open_files(mut self, max_open: usize) -> Self {
+        self.max_open = max_open.max(1);
+        self
+    }
+
+    /// Value of the field that selects the output file. Characters that are not allowed in file
+    /// names are replaced by "_". If there is no year, the year of the date is used.
+    fn value(&self, entry: &biblatex::Entry) -> String {
+        let value = if self.field == "entrytype" {
+            entry.entry_type.to_biblatex().to_string()
+        } else if let Some(x) = entry.get(&self.field) {
+            biblatex::ChunksExt::format_verbatim(x)
+        } else if let Some(x) = entry.get("date").filter(|_| self.field == "year") {
+            biblatex::ChunksExt::format_verbatim(x)
+                .chars()
+                .take(4)
+                .collect()
+        } else {
+            String::new()
+        };
+
+        let ret: String = value
+            .trim()
+            .chars()
+            .map(|c| {
+                if c.is_control() || "/\\:*?\"<>|".contains(c) {
+                    '_'
+                } else {
+                    c
+                }
+            })
+            .collect();
+        if ret.is_empty() || ret.chars().all(|c| c == '.') {
+            String::from("unknown")
+        } else {
+            ret
+        }
+    }
+
+    /// Writer of the output file of a value. Files are created on first use and reopened for
+    /// appending if they were closed.
+    fn writer(&mut self, value: &str) -> Result<&mut dyn BibWrite, Error> {
+        if let Some(x) = self.open.iter().position(|x| x == value) {
+            let value = self.open.remove(x).unwrap();
+            self.open.push_back(value);
+        } else {
+            if self.open.len() >= self.max_open {
+                if let Some(x) = self.open.pop_front() {
+                    // dropping the writer flushes and closes the file
+                    self.files.get_mut(&x).unwrap().writer = None;
+                }
+            }
+
+            let append = self.files.contains_key(value);
+            let path = match self.files.get(value) {
+                Some(x) => x.path.clone(),
+                None => std::path::PathBuf::from(
+                    self.placeholder
+                        .replace_all(&self.template, regex::NoExpand(value))
+                        .as_ref(),
+                ),
+            };
+            let mut writer = (self.create)(&path, append)?;
+            if let Some(x) = &self.macros {
+                if append {
+                    writer.use_macros(x);
+                } else {
+                    writer.set_macros(x)?;
+                }
+            }
+            self.files
+                .entry(String::from(value))
+                .or_insert(OutputFile {
+                    path,
+                    entries: 0,
+                    writer: None,
+                })
+                .writer = Some(writer);
+            self.open.push_back(String::from(value));
+        }
+
+        Ok(self
+            .files
+            .get_mut(value)
+            .and_then(|x| x.writer.as_deref_mut())
+            .unwrap())
+    }
+}
+
+impl BibWrite for SplitWriter {
+    fn write(&mut self, entry: &biblatex::Entry) -> Result<biblatex::Entry, Error> {
+        let value = self.value(entry);
+
+        let key = crate::bibwriter::unique_key(&mut self.keys, &entry.key);
+        let written = if key != entry.key {
+            self.renamed.push((entry.key.clone(), key.clone()));
+            let mut entry = entry.clone();
+            entry.key = key;
+            self.writer(&value)?.write(&entry)?
+        } else {
+            self.writer(&value)?.write(entry)?
+        };
+        self.files.get_mut(&value).unwrap().entries += 1;
+
+        Ok(written)
+    }
+
+    fn get_num_written_entries(&self) -> usize {
+        self.files.values().map(|x| x.entries).sum()
+    }
+
+    fn set_macros(&mut self, macros: &crate::macros::StringMacros) -> Result<(), Error> {
+        for writer in self.files.values_mut().filter_map(|x| x.writer.as_mut()) {
+            writer.set_macros(macros)?;
+        }
+        self.macros = Some(macros.clone());
+        Ok(())
+    }
+
+    fn get_written_files(&self) -> Vec<(std::path::PathBuf, usize)> {
+        self.files
+            .values()
+            .map(|x| (x.path.clone(), x.entries))
+            .collect()
+    }
+
+    fn get_renamed_keys(&self) -> Vec<(String, String)> {
+        self.renamed.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writer that only counts the entries
+    struct CountingWriter(usize);
+
+    impl BibWrite for CountingWriter {
+        fn write(&mut self, entry: &biblatex::Entry) -> Result<biblatex::Entry, Error> {
+            self.0 += 1;
+            Ok(entry.clone())
+        }
+        fn get_num_written_entries(&self) -> usize {
+            self.0
+        }
+    }
+
+    fn entry(year: Option<&str>) -> biblatex::Entry {
+        let mut ret = biblatex::Entry::new(String::from("test1"), biblatex::EntryType::Article);
+        if let Some(x) = year {
+            ret.set(
+                "year",
+                vec![biblatex::Spanned::detached(biblatex::Chunk::Normal(
+                    String::from(x),
+                ))],
+            );
+        }
+        ret
+    }
+
+    #[test]
+    fn test_split_writer() {
+        let mut writer = SplitWriter::new(
+            "year",
+            "out/{year}.bib",
+            Box::new(|_, _| Ok(Box::new(CountingWriter(0)))),
+        )
+        .unwrap();
+        writer.write(&entry(Some("2020"))).unwrap();
+        writer.write(&entry(Some("2021"))).unwrap();
+        writer.write(&entry(Some("2020"))).unwrap();
+        writer.write(&entry(None)).unwrap();
+        writer.write(&entry(Some("20/21"))).unwrap();
+
+        assert_eq!(writer.get_num_written_entries(), 5);
+        assert_eq!(
+            writer.get_written_files(),
+            vec![
+                (std::path::PathBuf::from("out/2020.bib"), 2),
+                (std::path::PathBuf::from("out/2021.bib"), 1),
+                (std::path::PathBuf::from("out/20_21.bib"), 1),
+                (std::path::PathBuf::from("out/unknown.bib"), 1),
+            ]
+        );
+
+        assert!(SplitWriter::new("year", "out.bib", Box::new(|_, _| unreachable!())).is_err());
+    }
+
+    #[test]
+    fn test_max_open_files() {
+        // paths of the created writers and whether they append
+        let created = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let log = created.clone();
+        let mut writer = SplitWriter::new(
+            "year",
+            "out/{year}.bib",
+            Box::new(move |path, append| {
+                log.borrow_mut().push((path.display().to_string(), append));
+                Ok(Box::new(CountingWriter(0)))
+            }),
+        )
+        .unwrap()
+        .with_max_open_files(2);
+        for year in ["2020", "2021", "2020", "2022", "2021", "2020"] {
+            writer.write(&entry(Some(year))).unwrap();
+        }
+
+        assert_eq!(
+            *created.borrow(),
+            vec![
+                (String::from("out/2020.bib"), false),
+                (String::from("out/2021.bib"), false),
+                (String::from("out/2022.bib"), false),
+                (String::from("out/2021.bib"), true),
+                (String::from("out/2020.bib"), true),
+            ]
+        );
+        assert_eq!(
+            writer.get_written_files(),
+            vec![
+                (std::path::PathBuf::from("out/2020.bib"), 3),
+                (std::path::PathBuf::from("out/2021.bib"), 2),
+                (std::path::PathBuf::from("out/2022.bib"), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unique_keys() {
+        let mut writer = SplitWriter::new(
+            "year",
+            "out/{year}.bib",
+            Box::new(|_, _| Ok(Box::new(CountingWriter(0)))),
+        )
+        .unwrap();
+
+        // keys are unique across all files
+        assert_eq!(writer.write(&entry(Some("2020"))).unwrap().key, "test1");
+        assert_eq!(writer.write(&entry(Some("2021"))).unwrap().key, "test1_2");
+        assert_eq!(
+            writer.get_renamed_keys(),
+            vec![(String::from("test1"), String::from("test1_2"))]
+        );
+    }
+}
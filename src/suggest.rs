@@ -0,0 +1,504 @@
+use crate::args;
+use crate::converter;
+use crate::csvreader;
+use crate::glob;
+use crate::profile;
+use crate::Error;
+
+/// Pseudo field of the first page, combined with the last page to `pages`
+const START_PAGE: &str = "startpage";
+
+/// Pseudo field of the last page
+const END_PAGE: &str = "endpage";
+
+/// Minimum similarity of a column name and a field name or alias for a fuzzy match
+const MIN_SIMILARITY: f64 = 0.8;
+
+/// Minimum length of names that are matched fuzzily, shorter names like "PY" have to match
+/// exactly
+const MIN_FUZZY_LENGTH: usize = 5;
+
+/// Fields and alternative column names used by reference managers and databases like Web of
+/// Science, Scopus, IEEE Xplore, Zotero, or RIS files. Earlier fields win if a column matches
+/// several fields equally well.
+const ALIASES: [(&str, &[&str]); 35] = [
+    (
+        "entrytype",
+        &[
+            "type",
+            "entry type",
+            "item type",
+            "document type",
+            "reference type",
+            "publication type",
+            "dt",
+            "pt",
+            "ty",
+        ],
+    ),
+    (
+        "bibtexkey",
+        &["key", "citation key", "cite key", "bibtex key"],
+    ),
+    (
+        "author",
+        &[
+            "authors",
+            "author names",
+            "author full names",
+            "creator",
+            "creators",
+            "au",
+            "af",
+            "a1",
+        ],
+    ),
+    ("editor", &["editors", "ed"]),
+    (
+        "title",
+        &[
+            "document title",
+            "article title",
+            "primary title",
+            "item title",
+            "ti",
+            "t1",
+        ],
+    ),
+    (
+        "journal",
+        &[
+            "journaltitle",
+            "journal title",
+            "journal name",
+            "publication title",
+            "publication name",
+            "source title",
+            "secondary title",
+            "source",
+            "so",
+            "jo",
+            "jf",
+            "t2",
+        ],
+    ),
+    ("booktitle", &["book title", "proceedings title", "bt"]),
+    ("eventtitle", &["conference name", "meeting name", "ct"]),
+    ("eventdate", &["conference date", "meeting date", "cy"]),
+    (
+        "year",
+        &[
+            "publication year",
+            "pub year",
+            "year published",
+            "py",
+            "yr",
+            "y1",
+        ],
+    ),
+    ("date", &["publication date", "date published", "pd", "da"]),
+    ("month", &["publication month"]),
+    ("volume", &["vol", "vl", "vo"]),
+    ("number", &["issue", "issue number", "is", "no"]),
+    ("pages", &["page range", "page numbers", "pp"]),
+    (
+        START_PAGE,
+        &[
+            "start page",
+            "first page",
+            "beginning page",
+            "page start",
+            "bp",
+            "sp",
+        ],
+    ),
+    (
+        END_PAGE,
+        &["end page", "last page", "ending page", "page end", "ep"],
+    ),
+    (
+        "pagetotal",
+        &["page count", "number of pages", "num pages", "pg"],
+    ),
+    ("abstract", &["summary", "abstract note", "ab", "n2"]),
+    (
+        "keywords",
+        &[
+            "keyword",
+            "author keywords",
+            "tags",
+            "manual tags",
+            "subject terms",
+            "subjects",
+            "de",
+            "kw",
+        ],
+    ),
+    ("doi", &["digital object identifier", "di", "do"]),
+    ("url", &["link", "pdf link", "web link", "ur"]),
+    (
+        "urldate",
+        &["access date", "accessed", "date accessed", "y2"],
+    ),
+    ("file", &["file attachments", "attachments", "pdf"]),
+    ("isbn", &["isbns", "bn"]),
+    ("issn", &["issns", "sn"]),
+    ("publisher", &["publishers", "pu", "pb"]),
+    (
+        "location",
+        &["address", "place published", "publisher city", "city", "pi"],
+    ),
+    ("language", &["lang", "la"]),
+    ("note", &["notes", "comment", "comments", "extra", "n1"]),
+    ("series", &["series title", "se"]),
+    ("edition", &[]),
+    ("institution", &[]),
+    ("school", &["university"]),
+    ("chapter", &[]),
+];
+
+/// A guessed mapping of a field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suggestion {
+    /// Name of the field
+    pub field: String,
+    /// Template of the field, e.g., "[[Authors]]"
+    pub template: String,
+    /// Did a column name only match approximately?
+    pub fuzzy: bool,
+}
+
+/// Guess the mapping of CSV columns to fields by comparing the column names with field names and
+/// common aliases like "Publication Year" or "PY". Case, whitespace, and punctuation are
+/// ignored, and names with small typos match, too. Fields that are in `mapping` and columns that
+/// are used by its templates are skipped. Columns of the first and the last page are combined to
+/// `pages`. The suggestions are in the order of the columns.
+pub fn suggest(
+    headers: &[String],
+    mapping: &std::collections::HashMap<String, String>,
+) -> Vec<Suggestion> {
+    let used: Vec<String> = mapping
+        .values()
+        .flat_map(|x| converter::template_columns(x))
+        .collect();
+    let is_mapped = |field: &str| {
+        mapping.contains_key(field)
+            || ((field == START_PAGE || field == END_PAGE) && mapping.contains_key("pages"))
+    };
+
+    // score every pair of column and field, exact matches get a score of 100
+    let mut candidates: Vec<(u32, usize, usize)> = Vec::new();
+    for (column, header) in headers.iter().enumerate() {
+        if used.contains(header) {
+            continue;
+        }
+        let name = compact(header);
+        for (priority, (field, aliases)) in ALIASES.iter().enumerate() {
+            if is_mapped(field) {
+                continue;
+            }
+            let score = std::iter::once(*field)
+                .chain(aliases.iter().copied())
+                .map(|x| score(&name, &compact(x)))
+                .max()
+                .unwrap_or_default();
+            if score > 0 {
+                candidates.push((score, column, priority));
+            }
+        }
+    }
+    candidates.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+    // take the best matches, every field and every column only once
+    let mut matches: Vec<(usize, usize, bool)> = Vec::new();
+    for (score, column, priority) in candidates {
+        if matches
+            .iter()
+            .any(|(x, y, _)| *x == column || *y == priority)
+        {
+            continue;
+        }
+        matches.push((column, priority, score < 100));
+    }
+    matches.sort();
+
+    let find = |field: &str| {
+        matches
+            .iter()
+            .find(|(_, priority, _)| ALIASES[*priority].0 == field)
+            .map(|(column, _, fuzzy)| (&headers[*column], *fuzzy))
+    };
+    let mut ret = Vec::new();
+    for (column, priority, fuzzy) in &matches {
+        let (field, template, fuzzy) = match ALIASES[*priority].0 {
+            START_PAGE => match find(END_PAGE) {
+                Some((end, end_fuzzy)) => (
+                    "pages",
+                    format!("[[{}]]--[[{}]]", headers[*column], end),
+                    *fuzzy || end_fuzzy,
+                ),
+                None => ("pages", format!("[[{}]]", headers[*column]), *fuzzy),
+            },
+            END_PAGE => continue,
+            x => (x, format!("[[{}]]", headers[*column]), *fuzzy),
+        };
+        if ret.iter().any(|x: &Suggestion| x.field == field) {
+            continue;
+        }
+        ret.push(Suggestion {
+            field: String::from(field),
+            template,
+            fuzzy,
+        });
+    }
+    ret
+}
+
+/// Columns whose names match a field or one of its aliases, best matches first. For `pages`,
+/// columns of the first and the last page match, too.
+pub fn matching_columns(field: &str, headers: &[String]) -> Vec<usize> {
+    let names: Vec<&str> = ALIASES
+        .iter()
+        .filter(|(x, _)| *x == field || (field == "pages" && (*x == START_PAGE || *x == END_PAGE)))
+        .flat_map(|(x, aliases)| std::iter::once(*x).chain(aliases.iter().copied()))
+        .chain(std::iter::once(field))
+        .collect();
+
+    let mut ret: Vec<(u32, usize)> = headers
+        .iter()
+        .enumerate()
+        .filter_map(|(i, header)| {
+            let name = compact(header);
+            let score = names
+                .iter()
+                .map(|x| score(&name, &compact(x)))
+                .max()
+                .unwrap_or_default();
+            (score > 0).then_some((score, i))
+        })
+        .collect();
+    ret.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    ret.into_iter().map(|x| x.1).collect()
+}
+
+/// Add the suggestions for fields that are not mapped yet
+pub fn apply(suggestions: &[Suggestion], mapping: &mut std::collections::HashMap<String, String>) {
+    for x in suggestions {
+        mapping
+            .entry(x.field.clone())
+            .or_insert_with(|| x.template.clone());
+    }
+}
+
+/// Profile with the suggestions. Fuzzy matches are listed in a comment to be checked.
+pub fn to_profile(suggestions: &[Suggestion], source: &str) -> profile::Profile {
+    let mut ret = profile::Profile::new().with_comment(&format!("Guessed from \"{}\"", source));
+    let fuzzy: Vec<&str> = suggestions
+        .iter()
+        .filter(|x| x.fuzzy)
+        .map(|x| x.field.as_str())
+        .collect();
+    if !fuzzy.is_empty() {
+        ret = ret.with_comment(&format!(
+            "Please check these fields, their columns only have similar names: {}",
+            fuzzy.join(", ")
+        ));
+    }
+    for x in suggestions {
+        ret.add(&x.field, &x.template);
+    }
+    ret
+}
+
+/// Headers of the first input file
+pub fn read_headers(config: &args::Config) -> Result<Option<Vec<String>>, Error> {
+    let path = match config.files_input.first() {
+        Some(x) => glob::expand(&x.to_string_lossy())?.into_iter().next(),
+        None => None,
+    };
+    let path = match path {
+        Some(x) => x,
+        None => return Ok(None),
+    };
+    let file_input = std::fs::File::open(&path)
+        .map_err(|e| Error::io(format!("Could not open csv file: {}", path.display()), e))?;
+    let reader = csvreader::Reader::new(&file_input, &config.csv_delimiter, config.csv_lazy);
+    Ok(Some(reader.headers().to_vec()))
+}
+
+/// Print a profile with the suggested mapping of the first input file
+pub fn run<W: std::io::Write>(config: &args::Config, out: &mut W) -> Result<(), Error> {
+    let headers =
+        read_headers(config)?.ok_or_else(|| Error::Config(String::from("No input file found")))?;
+    let suggestions = suggest(&headers, &config.csv_field_mapping);
+    let source = config.files_input[0].display().to_string();
+    to_profile(&suggestions, &source)
+        .write(out, &headers)
+        .map_err(|e| Error::io(String::from("Could not write output"), e))
+}
+
+/// Similarity of two compact names between 0 and 100, see `suggest`
+fn score(name: &str, alias: &str) -> u32 {
+    if name == alias {
+        return 100;
+    }
+    if name.chars().count() < MIN_FUZZY_LENGTH || alias.chars().count() < MIN_FUZZY_LENGTH {
+        return 0;
+    }
+    let length = name.chars().count().max(alias.chars().count());
+    let similarity = 1.0 - distance(name, alias) as f64 / length as f64;
+    match similarity >= MIN_SIMILARITY {
+        // exact matches always win
+        true => ((similarity * 100.0) as u32).min(99),
+        false => 0,
+    }
+}
+
+/// Lowercase a name and remove everything except letters and digits, e.g., "Author(s)" to
+/// "authors"
+fn compact(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Levenshtein distance of two strings
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(x != *y);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(names: &[&str]) -> Vec<String> {
+        names.iter().map(|x| String::from(*x)).collect()
+    }
+
+    fn fields(suggestions: &[Suggestion]) -> Vec<(&str, &str, bool)> {
+        suggestions
+            .iter()
+            .map(|x| (x.field.as_str(), x.template.as_str(), x.fuzzy))
+            .collect()
+    }
+
+    #[test]
+    fn test_distance() {
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(distance("title", "title"), 0);
+    }
+
+    #[test]
+    fn test_suggest() {
+        let headers = headers(&[
+            "Document Title",
+            "Authors",
+            "Publication Title",
+            "Publication Year",
+            "Start Page",
+            "End Page",
+            "DOI",
+            "Abstrakt",
+            "Funding Information",
+            "Author(s)",
+        ]);
+        let suggestions = suggest(&headers, &std::collections::HashMap::new());
+        assert_eq!(
+            fields(&suggestions),
+            vec![
+                ("title", "[[Document Title]]", false),
+                ("author", "[[Authors]]", false),
+                ("journal", "[[Publication Title]]", false),
+                ("year", "[[Publication Year]]", false),
+                ("pages", "[[Start Page]]--[[End Page]]", false),
+                ("doi", "[[DOI]]", false),
+                ("abstract", "[[Abstrakt]]", true),
+            ]
+        );
+
+        // fields and columns of the given mapping are skipped
+        let mut mapping = std::collections::HashMap::new();
+        mapping.insert(String::from("author"), String::from("[[Author(s)]]"));
+        mapping.insert(String::from("note"), String::from("[[DOI]]"));
+        mapping.insert(String::from("pages"), String::from("[[Start Page]]"));
+        let suggestions = suggest(&headers[..8], &mapping);
+        assert_eq!(
+            fields(&suggestions),
+            vec![
+                ("title", "[[Document Title]]", false),
+                ("journal", "[[Publication Title]]", false),
+                ("year", "[[Publication Year]]", false),
+                ("abstract", "[[Abstrakt]]", true),
+            ]
+        );
+
+        // short tags of Web of Science
+        let suggestions = suggest(
+            &self::headers(&["PT", "AU", "TI", "SO", "PY", "BP", "EP", "DI"]),
+            &std::collections::HashMap::new(),
+        );
+        assert_eq!(
+            fields(&suggestions),
+            vec![
+                ("entrytype", "[[PT]]", false),
+                ("author", "[[AU]]", false),
+                ("title", "[[TI]]", false),
+                ("journal", "[[SO]]", false),
+                ("year", "[[PY]]", false),
+                ("pages", "[[BP]]--[[EP]]", false),
+                ("doi", "[[DI]]", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_matching_columns() {
+        let headers = headers(&["Source Title", "Start Page", "Titles", "End Page", "Title"]);
+        assert_eq!(matching_columns("title", &headers), vec![4, 2]);
+        assert_eq!(matching_columns("pages", &headers), vec![1, 3]);
+        assert_eq!(matching_columns("journal", &headers), vec![0]);
+        assert_eq!(
+            matching_columns("howpublished", &headers),
+            Vec::<usize>::new()
+        );
+    }
+
+    #[test]
+    fn test_to_profile() {
+        let suggestions = suggest(
+            &headers(&["Titles", "Authors"]),
+            &std::collections::HashMap::new(),
+        );
+        let mut out = Vec::new();
+        to_profile(&suggestions, "test.csv")
+            .write(&mut out, &headers(&["Titles", "Authors", "Extra Info"]))
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\
+# csv2bibtex mapping profile, use it with --profile FILE
+# One field per line like --field-mapping: FIELD = TEMPLATE
+# Guessed from \"test.csv\"
+# Please check these fields, their columns only have similar names: title
+
+title = [[Titles]]
+author = [[Authors]]
+
+# Columns without field
+# extra_info = [[Extra Info]]
+"
+        );
+    }
+}
@@ -0,0 +1,413 @@
+use crate::args;
+use crate::converter;
+use crate::csvreader;
+use crate::glob;
+use crate::profile;
+use crate::suggest;
+use crate::Error;
+
+/// Fields that are asked for in this order
+const FIELDS: [&str; 14] = [
+    "entrytype",
+    "author",
+    "title",
+    "year",
+    "journal",
+    "booktitle",
+    "volume",
+    "number",
+    "pages",
+    "publisher",
+    "doi",
+    "url",
+    "abstract",
+    "keywords",
+];
+
+/// Maximum length of the sample values in the list of columns
+const SAMPLE_LENGTH: usize = 40;
+
+/// Interactive creation of a mapping profile. For every important field, the matching columns
+/// are offered and the first row of the CSV file is converted as a preview.
+pub struct Wizard<'a, R: std::io::BufRead, W: std::io::Write> {
+    config: &'a args::Config,
+    input: R,
+    output: W,
+    // Columns of the CSV file
+    headers: Vec<String>,
+    // Cells of the first row by their column name, if the file has a row
+    sample: Option<std::collections::HashMap<String, String>>,
+    profile: profile::Profile,
+}
+
+impl<'a, R: std::io::BufRead, W: std::io::Write> Wizard<'a, R, W> {
+    pub fn new(
+        config: &'a args::Config,
+        input: R,
+        output: W,
+        headers: &[String],
+        sample: Option<std::collections::HashMap<String, String>>,
+    ) -> Self {
+        Self {
+            config,
+            input,
+            output,
+            headers: headers.to_vec(),
+            sample,
+            profile: profile::Profile::new(),
+        }
+    }
+
+    /// Ask for all fields and return the resulting profile
+    pub fn run(&mut self) -> Result<profile::Profile, Error> {
+        self.print_columns()?;
+        let suggestions = suggest::suggest(&self.headers, &self.config.csv_field_mapping);
+
+        // the converter of the previews is created once, e.g., journal lists are read only once
+        let mut mapping = self.config.csv_field_mapping.clone();
+        let mut verbatim_fields = self.config.verbatim_fields.clone();
+        let mut converter =
+            crate::create_converter(self.config, &mut mapping, &mut verbatim_fields)?;
+
+        let mut asked: Vec<String> = Vec::new();
+        for field in FIELDS {
+            asked.push(String::from(field));
+            let default = match self.config.csv_field_mapping.get(field) {
+                Some(x) => Some(x.clone()),
+                None => suggestions
+                    .iter()
+                    .find(|x| x.field == field)
+                    .map(|x| x.template.clone()),
+            };
+            self.ask_field(&mut converter, field, default)?;
+        }
+
+        loop {
+            let field = self.ask("Further field (empty to finish): ")?;
+            if field.is_empty() {
+                break;
+            }
+            let default = self.config.csv_field_mapping.get(&field).cloned();
+            self.ask_field(&mut converter, &field, default)?;
+            asked.push(field);
+        }
+
+        // keep the mapping of the command line for all fields that were not asked for
+        let mut mapping: Vec<(&String, &String)> = self
+            .config
+            .csv_field_mapping
+            .iter()
+            .filter(|(field, _)| !asked.contains(field))
+            .collect();
+        mapping.sort();
+        for (field, template) in mapping {
+            self.profile.add(field, template);
+        }
+
+        Ok(self.profile.clone())
+    }
+
+    /// Ask for the file to save the profile to and save it. Returns the path of the file.
+    pub fn save(
+        &mut self,
+        profile: &profile::Profile,
+        default: &std::path::Path,
+    ) -> Result<std::path::PathBuf, Error> {
+        let answer = self.ask(&format!(
+            "Save profile to (default: {}): ",
+            default.display()
+        ))?;
+        let path = match answer.is_empty() {
+            true => default.to_path_buf(),
+            false => std::path::PathBuf::from(answer),
+        };
+
+        // existing files are only overwritten after asking
+        let file = match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                let answer = self.ask(&format!(
+                    "File \"{}\" exists already. Overwrite it? [y/N] ",
+                    path.display()
+                ))?;
+                if !answer.eq_ignore_ascii_case("y") && !answer.eq_ignore_ascii_case("yes") {
+                    return self.save(profile, default);
+                }
+                std::fs::File::create(&path)
+            }
+            x => x,
+        };
+        let mut file = std::io::BufWriter::new(file.map_err(|e| {
+            Error::io(
+                format!("Could not open file for writing: {}", path.display()),
+                e,
+            )
+        })?);
+        profile
+            .write(&mut file, &self.headers)
+            .map_err(|e| Error::io(format!("Could not write profile: {}", path.display()), e))?;
+        self.print(&format!(
+            "Saved profile to \"{}\", use it with --profile {}.\n",
+            path.display(),
+            path.display()
+        ))?;
+        Ok(path)
+    }
+
+    /// List all columns with their value in the first row
+    fn print_columns(&mut self) -> Result<(), Error> {
+        let width = self
+            .headers
+            .iter()
+            .map(|x| x.chars().count())
+            .max()
+            .unwrap_or_default();
+        let digits = self.headers.len().to_string().len();
+        let mut text = String::from("Columns and their values in the first row:\n");
+        for (i, column) in self.headers.iter().enumerate() {
+            let value = self
+                .sample
+                .as_ref()
+                .and_then(|x| x.get(column))
+                .map(|x| truncate(x))
+                .unwrap_or_default();
+            let line = format!("{:>digits$}  {:width$}  {}", i + 1, column, value);
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text.push_str(
+            "\nFor every field, enter the number of a column, a template like \
+             \"[[Start Page]]--[[End Page]]\", \"-\" to leave the field empty, or nothing to \
+             take the default.\n",
+        );
+        self.print(&text)
+    }
+
+    /// Ask for the template of a field until the answer is valid, and add it to the profile
+    fn ask_field(
+        &mut self,
+        converter: &mut converter::FieldConverter,
+        field: &str,
+        default: Option<String>,
+    ) -> Result<(), Error> {
+        let matching: Vec<String> = suggest::matching_columns(field, &self.headers)
+            .into_iter()
+            .map(|i| format!("{} {}", i + 1, self.headers[i]))
+            .collect();
+        let mut question = format!("\n{}", field);
+        if !matching.is_empty() {
+            question.push_str(&format!(", matching columns: {}", matching.join(", ")));
+        }
+        question.push('\n');
+        match &default {
+            Some(x) => question.push_str(&format!("Template (default: {}): ", x)),
+            None => question.push_str("Template (default: empty): "),
+        }
+
+        let template = loop {
+            let answer = self.ask(&question)?;
+            match self.parse_answer(&answer, default.as_deref()) {
+                Ok(x) => break x,
+                Err(e) => self.print(&format!("{}.\n", e))?,
+            }
+        };
+        if let Some(x) = template {
+            self.profile.add(field, &x);
+            converter.set_template(field, &x)?;
+            self.preview(converter)?;
+        }
+        Ok(())
+    }
+
+    /// Template of an answer, `None` if the field stays empty
+    fn parse_answer(&self, answer: &str, default: Option<&str>) -> Result<Option<String>, Error> {
+        if answer.is_empty() {
+            return Ok(default.map(String::from));
+        }
+        if answer == "-" {
+            return Ok(None);
+        }
+        if let Ok(i) = answer.parse::<usize>() {
+            return match self.headers.get(i.wrapping_sub(1)) {
+                Some(x) => Ok(Some(format!("[[{}]]", x))),
+                None => Err(Error::Config(format!("There is no column {}", i))),
+            };
+        }
+
+        converter::check_template(answer)?;
+        for column in converter::template_columns(answer) {
+            if !self.headers.contains(&column) {
+                return Err(Error::Config(format!("Unknown CSV column \"{}\"", column)));
+            }
+        }
+        Ok(Some(String::from(answer)))
+    }
+
+    /// Print the first row converted with the current profile
+    fn preview(&mut self, converter: &converter::FieldConverter) -> Result<(), Error> {
+        let sample = match &self.sample {
+            Some(x) => x.clone(),
+            None => return Ok(()),
+        };
+        let entry = converter.convert_fields(sample, String::from("entry_0"));
+
+        let mut buffer = Vec::new();
+        let result = crate::format_writer(self.config, &mut buffer, converter.verbatim_fields())
+            .write(&entry);
+        let text = match result {
+            Ok(_) => String::from_utf8_lossy(&buffer).into_owned(),
+            Err(e) => format!("{}.\n", e),
+        };
+        self.print(&format!("Preview of the first row:\n{}", text))
+    }
+
+    /// Print a question and read the answer without surrounding whitespace
+    fn ask(&mut self, question: &str) -> Result<String, Error> {
+        self.print(question)?;
+        let mut answer = String::new();
+        let bytes = self
+            .input
+            .read_line(&mut answer)
+            .map_err(|e| Error::io(String::from("Could not read answer"), e))?;
+        if bytes == 0 {
+            return Err(Error::Config(String::from("Wizard aborted")));
+        }
+        Ok(String::from(answer.trim()))
+    }
+
+    fn print(&mut self, text: &str) -> Result<(), Error> {
+        write!(self.output, "{}", text)
+            .and_then(|_| self.output.flush())
+            .map_err(|e| Error::io(String::from("Could not write output"), e))
+    }
+}
+
+/// Truncate long values and replace line breaks by spaces
+fn truncate(value: &str) -> String {
+    let value = value.trim().replace(['\r', '\n'], " ");
+    match value.chars().count() > SAMPLE_LENGTH {
+        true => format!(
+            "{}...",
+            value.chars().take(SAMPLE_LENGTH - 3).collect::<String>()
+        ),
+        false => value,
+    }
+}
+
+/// Create a mapping profile for the first input file interactively and save it
+pub fn run<R: std::io::BufRead, W: std::io::Write>(
+    config: &args::Config,
+    input: R,
+    output: W,
+) -> Result<(), Error> {
+    let path = match config.files_input.first() {
+        Some(x) => glob::expand(&x.to_string_lossy())?.into_iter().next(),
+        None => None,
+    }
+    .ok_or_else(|| Error::Config(String::from("No input file found")))?;
+    let file_input = std::fs::File::open(&path)
+        .map_err(|e| Error::io(format!("Could not open csv file: {}", path.display()), e))?;
+    let mut reader = csvreader::Reader::new(&file_input, &config.csv_delimiter, config.csv_lazy)
+        .with_skip(config.skip_rows);
+    let headers = reader.headers().to_vec();
+    let sample = reader.find_map(|x| x.ok()).map(|x| x.fields);
+
+    let mut wizard = Wizard::new(config, input, output, &headers, sample);
+    let profile = wizard.run()?;
+    // the profile is saved in the working directory by default, e.g., "export.profile"
+    let default = std::path::PathBuf::from(path.file_stem().unwrap_or_default());
+    wizard.save(&profile, &default.with_extension("profile"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wizard() {
+        let headers: Vec<String> = ["Document Title", "Authors", "PY", "Start Page", "End Page"]
+            .iter()
+            .map(|x| String::from(*x))
+            .collect();
+        let sample: std::collections::HashMap<String, String> = headers
+            .iter()
+            .cloned()
+            .zip(["Some Title", "Doe, Jane", "2021", "45", "67"].map(String::from))
+            .collect();
+        let config = args::Config {
+            mapping_defaults: false,
+            ..Default::default()
+        };
+
+        // entrytype: fixed type; author: default; title: unknown column, then column 1;
+        // year: skipped; pages: own template; further field "note"
+        let answers = [
+            "article",
+            "",
+            "[[Title]]",
+            "1",
+            "-",
+            "",
+            "",
+            "",
+            "",
+            "[[Start Page]]",
+            "",
+            "",
+            "",
+            "",
+            "",
+            "note",
+            "[[PY]]",
+            "",
+        ]
+        .map(|x| format!("{}\n", x))
+        .concat();
+        let mut output = Vec::new();
+        let profile = Wizard::new(
+            &config,
+            answers.as_bytes(),
+            &mut output,
+            &headers,
+            Some(sample),
+        )
+        .run()
+        .unwrap();
+        assert_eq!(
+            profile.mapping,
+            vec![
+                (String::from("entrytype"), String::from("article")),
+                (String::from("author"), String::from("[[Authors]]")),
+                (String::from("title"), String::from("[[Document Title]]")),
+                (String::from("pages"), String::from("[[Start Page]]")),
+                (String::from("note"), String::from("[[PY]]")),
+            ]
+        );
+
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("1  Document Title  Some Title\n"));
+        assert!(output.contains(
+            "pages, matching columns: 4 Start Page, 5 End Page\n\
+             Template (default: [[Start Page]]--[[End Page]]): "
+        ));
+        assert!(output.contains("Unknown CSV column \"Title\"."));
+        assert!(output.ends_with(
+            "Preview of the first row:\n\
+             @article{entry_0,\n\
+             author = {Doe, Jane},\n\
+             note = {2021},\n\
+             pages = {45},\n\
+             title = {Some Title},\n\
+             }\n\n\
+             Further field (empty to finish): "
+        ));
+
+        // the wizard stops at the end of the input
+        let result = Wizard::new(&config, "".as_bytes(), Vec::new(), &headers, None).run();
+        assert!(matches!(result, Err(Error::Config(_))));
+    }
+}
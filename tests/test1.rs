@@ -6,7 +6,7 @@ mod test_input1 {
     fn only_with_defaults() {
         // build config structure
         let config = csv2bibtex::args::Config {
-            file_input: std::path::PathBuf::from("./tests/test1-input1.csv"),
+            files_input: vec![std::path::PathBuf::from("./tests/test1-input1.csv")],
             file_output: std::path::PathBuf::from("./tests/tmp-test1-output1.bib"),
             ..Default::default()
         };
@@ -36,7 +36,7 @@ mod test_input1 {
     fn with_custom_fields() {
         // build config structure
         let mut config = csv2bibtex::args::Config {
-            file_input: std::path::PathBuf::from("./tests/test1-input1.csv"),
+            files_input: vec![std::path::PathBuf::from("./tests/test1-input1.csv")],
             file_output: std::path::PathBuf::from("./tests/tmp-test1-output2.bib"),
             ..Default::default()
         };
@@ -102,4 +102,508 @@ mod test_input1 {
         // clean up
         std::fs::remove_file("./tests/tmp-test1-output2.bib").unwrap();
     }
+
+    #[test]
+    fn unknown_column() {
+        // build config structure
+        let mut config = csv2bibtex::args::Config {
+            files_input: vec![std::path::PathBuf::from("./tests/test1-input1.csv")],
+            file_output: std::path::PathBuf::from("./tests/tmp-test1-output3.bib"),
+            ..Default::default()
+        };
+        config
+            .csv_field_mapping
+            .insert(String::from("title"), String::from("[[Title]]"));
+
+        // run main function
+        match csv2bibtex::run(&config) {
+            Err(e @ csv2bibtex::Error::UnknownColumn { .. }) => {
+                assert_eq!(e.exit_code(), 5);
+                assert_eq!(
+                    e.to_string(),
+                    "Field \"title\" refers to unknown CSV column \"Title\""
+                );
+            }
+            x => panic!("Unexpected result: {:?}", x),
+        }
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test1-output3.bib").unwrap();
+    }
+
+    #[test]
+    fn select_rows() {
+        // build config structure
+        let mut config = csv2bibtex::args::Config {
+            files_input: vec![std::path::PathBuf::from("./tests/test1-input1.csv")],
+            file_output: std::path::PathBuf::from("./tests/tmp-test1-output4.bib"),
+            filter: Some(String::from(
+                "[[Publication Year]]>=2010 && [[Document Identifier]]=~'^IEEE'",
+            )),
+            ..Default::default()
+        };
+
+        // only the first and the last row match
+        let summary = csv2bibtex::run(&config).unwrap();
+        assert_eq!((summary.rows, summary.entries, summary.filtered), (2, 2, 2));
+
+        // skip the first row
+        config.skip_rows = 1;
+        let summary = csv2bibtex::run(&config).unwrap();
+        assert_eq!((summary.rows, summary.entries, summary.filtered), (1, 1, 3));
+
+        // stop after the first match
+        config.skip_rows = 0;
+        config.limit_rows = Some(1);
+        let summary = csv2bibtex::run(&config).unwrap();
+        assert_eq!((summary.rows, summary.entries, summary.filtered), (1, 1, 0));
+
+        // invalid filters are configuration errors
+        config.filter = Some(String::from("[[Publication Year]]>="));
+        match csv2bibtex::run(&config) {
+            Err(e @ csv2bibtex::Error::Config(_)) => assert_eq!(e.exit_code(), 2),
+            x => panic!("Unexpected result: {:?}", x),
+        }
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test1-output4.bib").unwrap();
+    }
+
+    #[test]
+    fn split_output() {
+        // build config structure
+        let mut config = csv2bibtex::args::Config {
+            files_input: vec![std::path::PathBuf::from("./tests/test1-input1.csv")],
+            file_output: std::path::PathBuf::from("./tests/tmp-test1-split/{year}.bib"),
+            split_by: Some(String::from("year")),
+            ..Default::default()
+        };
+        config.csv_field_mapping.insert(
+            String::from("year"),
+            String::from("[[Publication Year]] if [[Publication Year]]!=2009"),
+        );
+
+        // run main function
+        let summary = csv2bibtex::run(&config).unwrap();
+        assert_eq!(summary.entries, 4);
+
+        // one file per year, the year is converted to a date in BibLaTeX
+        let mut files: Vec<String> = std::fs::read_dir("./tests/tmp-test1-split")
+            .unwrap()
+            .map(|x| x.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec!["2014.bib", "2018.bib", "2019.bib", "unknown.bib"]
+        );
+        let output = std::fs::read_to_string("./tests/tmp-test1-split/2018.bib").unwrap();
+        assert!(output.contains("date = {2018},"));
+
+        // clean up
+        std::fs::remove_dir_all("./tests/tmp-test1-split").unwrap();
+    }
+
+    #[test]
+    fn sort_output() {
+        // build config structure
+        let mut config = csv2bibtex::args::Config {
+            files_input: vec![std::path::PathBuf::from("./tests/test1-input1.csv")],
+            file_output: std::path::PathBuf::from("./tests/tmp-test1-output5.bib"),
+            sort_by: Some(String::from("year:desc")),
+            ..Default::default()
+        };
+        config
+            .csv_field_mapping
+            .insert(String::from("year"), String::from("[[Publication Year]]"));
+
+        // run main function
+        let summary = csv2bibtex::run(&config).unwrap();
+        assert_eq!(summary.entries, 4);
+
+        // newest entries first
+        let output = std::fs::read_to_string("./tests/tmp-test1-output5.bib").unwrap();
+        let keys: Vec<&str> = output
+            .lines()
+            .filter_map(|x| x.strip_prefix("@article{"))
+            .collect();
+        assert_eq!(keys, vec!["entry_3,", "entry_1,", "entry_0,", "entry_2,"]);
+
+        // invalid sort orders are configuration errors
+        config.sort_by = Some(String::from("year:newest"));
+        assert!(matches!(
+            csv2bibtex::run(&config),
+            Err(csv2bibtex::Error::Config(_))
+        ));
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test1-output5.bib").unwrap();
+    }
+
+    #[test]
+    fn formatted_output() {
+        // build config structure
+        let mut config = csv2bibtex::args::Config {
+            files_input: vec![std::path::PathBuf::from("./tests/test1-input1.csv")],
+            file_output: std::path::PathBuf::from("./tests/tmp-test1-output6.bib"),
+            limit_rows: Some(1),
+            ..Default::default()
+        };
+        for (field, template) in [
+            ("title", "[[Document Title]]"),
+            ("author", "[[Authors]]"),
+            ("journal", "[[Publication Title]]"),
+            ("year", "[[Publication Year]]"),
+        ] {
+            config
+                .csv_field_mapping
+                .insert(String::from(field), String::from(template));
+        }
+        config.format = csv2bibtex::bibwriter::Format {
+            field_order: vec![String::from("title"), String::from("author")],
+            indent: String::from("  "),
+            align: true,
+            type_case: csv2bibtex::bibwriter::TypeCase::Upper,
+            delimiter: csv2bibtex::bibwriter::Delimiter::Quotes,
+            trailing_comma: false,
+        };
+
+        // run main function
+        csv2bibtex::run(&config).unwrap();
+
+        let output = std::fs::read_to_string("./tests/tmp-test1-output6.bib").unwrap();
+        assert_eq!(
+            output,
+            "@ARTICLE{entry_0,\n  \
+             title        = \"Smart phone monitoring of second heart sound split\",\n  \
+             author       = \"S. R. Thiyagaraja; J. Vempati; R. Dantu; T. Sarma; S. Dantu\",\n  \
+             date         = \"2014\",\n  \
+             journaltitle = \"2014 36th Annual International Conference of the IEEE Engineering \
+             in Medicine and Biology Society\"\n}\n\n"
+        );
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test1-output6.bib").unwrap();
+    }
+
+    #[test]
+    fn string_macros() {
+        // build config structure
+        std::fs::write(
+            "./tests/tmp-test1-strings.bib",
+            "% conferences\n@string{embc = {2014 36th Annual International Conference of the IEEE \
+             Engineering in Medicine and Biology Society}}\n",
+        )
+        .unwrap();
+        let mut config = csv2bibtex::args::Config {
+            files_input: vec![std::path::PathBuf::from("./tests/test1-input1.csv")],
+            file_output: std::path::PathBuf::from("./tests/tmp-test1-output7.bib"),
+            output_type: csv2bibtex::args::OutputType::Bibtex,
+            string_fields: vec![String::from("journal")],
+            string_min_count: 1,
+            file_strings: Some(std::path::PathBuf::from("./tests/tmp-test1-strings.bib")),
+            month_macros: true,
+            ..Default::default()
+        };
+        config.csv_field_mapping.insert(
+            String::from("journal"),
+            String::from("[[Publication Title]]"),
+        );
+        config.csv_field_mapping.insert(
+            String::from("date"),
+            String::from("[[Publication Year]]-03"),
+        );
+
+        // run main function
+        csv2bibtex::run(&config).unwrap();
+
+        // the macros are defined at the beginning of the file
+        let output = std::fs::read_to_string("./tests/tmp-test1-output7.bib").unwrap();
+        let definitions: Vec<&str> = output
+            .lines()
+            .take_while(|x| x.starts_with("@string{"))
+            .collect();
+        assert_eq!(definitions.len(), 4);
+        assert!(definitions[0].starts_with("@string{embc = {2014 36th Annual"));
+        assert!(output.contains("@article{entry_0,\njournal = embc,\nmonth = mar,\n"));
+        assert!(output.contains("journal = litci2018,\n"));
+
+        // the macros are resolved when the file is parsed
+        let bibliography = biblatex::Bibliography::parse(&output).unwrap();
+        let entry = bibliography.get("entry_1").unwrap();
+        assert_eq!(
+            biblatex::ChunksExt::format_verbatim(entry.get("journal").unwrap()),
+            "Living in the Internet of Things: Cybersecurity of the IoT - 2018"
+        );
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test1-output7.bib").unwrap();
+        std::fs::remove_file("./tests/tmp-test1-strings.bib").unwrap();
+    }
+
+    #[test]
+    fn journal_titles() {
+        // build config structure
+        let mut config = csv2bibtex::args::Config {
+            files_input: vec![std::path::PathBuf::from("./tests/test1-input1.csv")],
+            file_output: std::path::PathBuf::from("./tests/tmp-test1-output8.bib"),
+            journal_lists: vec![std::path::PathBuf::from("./tests/test1-journals.csv")],
+            journal_form: csv2bibtex::journals::JournalForm::Both,
+            ..Default::default()
+        };
+        config.csv_field_mapping.insert(
+            String::from("journal"),
+            String::from("[[Publication Title]]"),
+        );
+
+        // run main function
+        csv2bibtex::run(&config).unwrap();
+
+        // journals in the list get an abbreviation, all others stay as they are
+        let output = std::fs::read_to_string("./tests/tmp-test1-output8.bib").unwrap();
+        let bibliography = biblatex::Bibliography::parse(&output).unwrap();
+        let value = |key: &str, field: &str| {
+            bibliography
+                .get(key)
+                .unwrap()
+                .get(field)
+                .map(biblatex::ChunksExt::format_verbatim)
+        };
+        assert_eq!(
+            value("entry_1", "shortjournal").as_deref(),
+            Some("Living Internet Things Cybersecur. IoT")
+        );
+        assert_eq!(
+            value("entry_3", "journaltitle").as_deref(),
+            Some("2019 IEEE/SICE International Symposium on System Integration (SII)")
+        );
+        assert_eq!(
+            value("entry_3", "shortjournal").as_deref(),
+            Some("Proc. IEEE/SICE Int. Symp. Syst. Integr.")
+        );
+        assert_eq!(value("entry_0", "shortjournal"), None);
+
+        // abbreviated titles only
+        config.journal_form = csv2bibtex::journals::JournalForm::Abbreviated;
+        csv2bibtex::run(&config).unwrap();
+        let output = std::fs::read_to_string("./tests/tmp-test1-output8.bib").unwrap();
+        assert!(output.contains("journaltitle = {Proc. IEEE/SICE Int. Symp. Syst. Integr.},"));
+        assert!(!output.contains("shortjournal"));
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test1-output8.bib").unwrap();
+    }
+
+    #[test]
+    fn normalized_identifiers() {
+        // build config structure
+        let mut config = csv2bibtex::args::Config {
+            files_input: vec![std::path::PathBuf::from("./tests/test1-input1.csv")],
+            file_output: std::path::PathBuf::from("./tests/tmp-test1-output9.bib"),
+            normalize_identifiers: true,
+            ..Default::default()
+        };
+        for (field, template) in [
+            ("doi", "https://doi.org/[[DOI]]"),
+            ("isbn", "[[ISBNs]]"),
+            ("issn", "[[ISSN]]"),
+            ("url", "[[PDF Link]]"),
+        ] {
+            config
+                .csv_field_mapping
+                .insert(String::from(field), String::from(template));
+        }
+
+        // run main function
+        csv2bibtex::run(&config).unwrap();
+
+        let output = std::fs::read_to_string("./tests/tmp-test1-output9.bib").unwrap();
+        let bibliography = biblatex::Bibliography::parse(&output).unwrap();
+        let entry = bibliography.get("entry_0").unwrap();
+        let value = |field: &str| {
+            entry
+                .get(field)
+                .map(biblatex::ChunksExt::format_verbatim)
+                .unwrap()
+        };
+        assert_eq!(value("doi"), "10.1109/embc.2014.6944050");
+        assert_eq!(value("isbn"), "9781424479290");
+        assert_eq!(value("issn"), "1558-4615");
+        assert_eq!(
+            value("url"),
+            "https://ieeexplore.ieee.org/stamp/stamp.jsp?arnumber=6944050"
+        );
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test1-output9.bib").unwrap();
+    }
+
+    #[test]
+    fn normalized_keywords() {
+        // build config structure
+        let mut config = csv2bibtex::args::Config {
+            files_input: vec![std::path::PathBuf::from("./tests/test1-input1.csv")],
+            file_output: std::path::PathBuf::from("./tests/tmp-test1-output10.bib"),
+            normalize_keywords: true,
+            ..Default::default()
+        };
+        config.csv_field_mapping.insert(
+            String::from("keywords"),
+            String::from("[[Author Keywords]] | Internet of Things"),
+        );
+
+        // run main function
+        csv2bibtex::run(&config).unwrap();
+
+        // duplicates are removed, the first spelling is kept
+        let output = std::fs::read_to_string("./tests/tmp-test1-output10.bib").unwrap();
+        let bibliography = biblatex::Bibliography::parse(&output).unwrap();
+        let keywords = |key: &str| {
+            biblatex::ChunksExt::format_verbatim(
+                bibliography.get(key).unwrap().get("keywords").unwrap(),
+            )
+        };
+        assert_eq!(keywords("entry_0"), "Internet of Things");
+        assert_eq!(
+            keywords("entry_1"),
+            "smart office, internet of things, co-design, occupancy monitoring, acceptability"
+        );
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test1-output10.bib").unwrap();
+    }
+
+    #[test]
+    fn dry_run() {
+        // build config structure
+        let config = csv2bibtex::args::Config {
+            files_input: vec![std::path::PathBuf::from("./tests/test1-input1.csv")],
+            file_output: std::path::PathBuf::from("./tests/tmp-test1-output11.bib"),
+            file_rejects: Some(std::path::PathBuf::from("./tests/tmp-test1-rejects11.csv")),
+            limit_rows: Some(2),
+            dry_run: true,
+            ..Default::default()
+        };
+
+        // entries are printed, but no file is written
+        let summary = csv2bibtex::run(&config).unwrap();
+        assert_eq!(summary.entries, 2);
+        assert!(!std::path::Path::new("./tests/tmp-test1-output11.bib").exists());
+        assert!(!std::path::Path::new("./tests/tmp-test1-rejects11.csv").exists());
+    }
+
+    #[test]
+    fn suggested_mapping() {
+        // build config structure
+        let mut config = csv2bibtex::args::Config {
+            files_input: vec![std::path::PathBuf::from("./tests/test1-input1.csv")],
+            file_output: std::path::PathBuf::from("./tests/tmp-test1-output12.bib"),
+            limit_rows: Some(1),
+            suggest_mapping: true,
+            ..Default::default()
+        };
+        config
+            .csv_field_mapping
+            .insert(String::from("note"), String::from("[[Publisher]]"));
+
+        // the guessed mapping complements the given one
+        csv2bibtex::run(&config).unwrap();
+        let output = std::fs::read_to_string("./tests/tmp-test1-output12.bib").unwrap();
+        assert!(output.contains("title = {Smart phone monitoring of second heart sound split},"));
+        assert!(output.contains("pages = {2181--2184},"));
+        assert!(output.contains("note = {IEEE},"));
+        assert!(!output.contains("publisher = "));
+
+        // a profile replaces the guessed mapping
+        std::fs::write(
+            "./tests/tmp-test1-profile12.txt",
+            "# test profile\ntitle = [[Publication Title]]\n",
+        )
+        .unwrap();
+        config.suggest_mapping = false;
+        config.file_profile = Some(std::path::PathBuf::from("./tests/tmp-test1-profile12.txt"));
+        csv2bibtex::run(&config).unwrap();
+        let output = std::fs::read_to_string("./tests/tmp-test1-output12.bib").unwrap();
+        assert!(output.contains("title = {2014 36th Annual International Conference"));
+        assert!(!output.contains("pages = "));
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test1-output12.bib").unwrap();
+        std::fs::remove_file("./tests/tmp-test1-profile12.txt").unwrap();
+    }
+
+    #[test]
+    fn conversion_report() {
+        // build config structure
+        let mut config = csv2bibtex::args::Config {
+            files_input: vec![std::path::PathBuf::from("./tests/test1-input1.csv")],
+            file_output: std::path::PathBuf::from("./tests/tmp-test1-output13.bib"),
+            file_report: Some(std::path::PathBuf::from("./tests/tmp-test1-report13.json")),
+            report_format: csv2bibtex::report::ReportFormat::Json,
+            ..Default::default()
+        };
+        for (field, template) in [
+            ("bibtexkey", "[[Publisher]]"),
+            ("title", "[[Document Title]]"),
+            ("volume", "[[Volume]]"),
+            ("journal", "[[Publication Title]]"),
+            ("note", "[[Funding Information]]"),
+        ] {
+            config
+                .csv_field_mapping
+                .insert(String::from(field), String::from(template));
+        }
+
+        // run main function
+        csv2bibtex::run(&config).unwrap();
+        let report = std::fs::read_to_string("./tests/tmp-test1-report13.json").unwrap();
+        assert!(report.contains("\"entries\": 4,"));
+        assert!(report.contains("\"article\": 4"));
+        assert!(report.contains("\"title\": {\"entries\": 4, \"fill_rate\": 100.0}"));
+        assert!(report.contains("\"volume\": {\"entries\": 1, \"fill_rate\": 25.0}"));
+        // the fields are counted as they are written in BibLaTeX
+        assert!(report.contains("\"journaltitle\": {\"entries\": 4, \"fill_rate\": 100.0}"));
+        assert!(!report.contains("\"journal\":"));
+        assert!(report.contains("{\"key\": \"IEEE\", \"renamed\": \"IEEE_2\"}"));
+        assert!(report.contains("\"empty_mappings\": [\n    \"note\"\n  ]"));
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test1-output13.bib").unwrap();
+        std::fs::remove_file("./tests/tmp-test1-report13.json").unwrap();
+    }
+
+    #[test]
+    fn mapping_wizard() {
+        // build config structure
+        let config = csv2bibtex::args::Config {
+            files_input: vec![std::path::PathBuf::from("./tests/test1-input1.csv")],
+            ..Default::default()
+        };
+
+        // take all defaults, then save the profile
+        let answers = format!("{}./tests/tmp-test1-profile14.txt\n", "\n".repeat(15));
+        let mut output = Vec::new();
+        csv2bibtex::wizard::run(&config, answers.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Saved profile to \"./tests/tmp-test1-profile14.txt\""));
+
+        let profile = std::fs::read_to_string("./tests/tmp-test1-profile14.txt").unwrap();
+        assert!(profile.contains("\ntitle = [[Document Title]]\n"));
+        assert!(profile.contains("\npages = [[Start Page]]--[[End Page]]\n"));
+        assert!(profile.contains("\n# ieee_terms = [[IEEE Terms]]\n"));
+
+        // an existing profile is only overwritten after asking
+        let answers = format!(
+            "{}./tests/tmp-test1-profile14.txt\nn\n./tests/tmp-test1-profile14.txt\ny\n",
+            "\n".repeat(15)
+        );
+        let mut output = Vec::new();
+        csv2bibtex::wizard::run(&config, answers.as_bytes(), &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output.matches("exists already. Overwrite it?").count(), 2);
+        assert!(output.contains("Saved profile to \"./tests/tmp-test1-profile14.txt\""));
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test1-profile14.txt").unwrap();
+    }
 }
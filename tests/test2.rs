@@ -10,7 +10,7 @@ mod test_input2 {
     fn fail_because_of_different_length() {
         // build config structure
         let config = csv2bibtex::args::Config {
-            file_input: std::path::PathBuf::from("./tests/test2-input1.csv"),
+            files_input: vec![std::path::PathBuf::from("./tests/test2-input1.csv")],
             file_output: std::path::PathBuf::from("./tests/tmp-test2-output1.bib"),
             csv_delimiter: String::from("\t"),
             ..Default::default()
@@ -24,7 +24,7 @@ mod test_input2 {
     fn should_work() {
         // build config structure
         let mut config = csv2bibtex::args::Config {
-            file_input: std::path::PathBuf::from("./tests/test2-input1.csv"),
+            files_input: vec![std::path::PathBuf::from("./tests/test2-input1.csv")],
             file_output: std::path::PathBuf::from("./tests/tmp-test2-output1.bib"),
             csv_delimiter: String::from("\t"),
             csv_lazy: true,
@@ -62,4 +62,70 @@ mod test_input2 {
         // clean up
         std::fs::remove_file("./tests/tmp-test2-output1.bib").unwrap();
     }
+
+    #[test]
+    fn write_rejected_rows() {
+        // build config structure
+        let config = csv2bibtex::args::Config {
+            files_input: vec![std::path::PathBuf::from("./tests/test2-input1.csv")],
+            file_output: std::path::PathBuf::from("./tests/tmp-test2-output2.bib"),
+            file_rejects: Some(std::path::PathBuf::from("./tests/tmp-test2-rejects2.csv")),
+            csv_delimiter: String::from("\t"),
+            ..Default::default()
+        };
+
+        // run main function, it stops at the first error
+        assert!(csv2bibtex::run(&config).is_err());
+
+        // the rejected row is written with its position and the reason
+        let rejects = std::fs::read_to_string("./tests/tmp-test2-rejects2.csv").unwrap();
+        let lines: Vec<&str> = rejects.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("line\tbyte\terror\tPT\tAU\t"));
+        assert!(lines[1].starts_with(
+            "2\t160\tFound 54 fields, but the previous record has 53 fields\tJ\tZhao, D. C.; Lin, F.\t"
+        ));
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test2-output2.bib").unwrap();
+        std::fs::remove_file("./tests/tmp-test2-rejects2.csv").unwrap();
+    }
+
+    #[test]
+    fn tolerate_some_errors() {
+        // build config structure
+        let mut config = csv2bibtex::args::Config {
+            files_input: vec![std::path::PathBuf::from("./tests/test2-short-rows.csv")],
+            file_output: std::path::PathBuf::from("./tests/tmp-test2-output3.bib"),
+            csv_delimiter: String::from("\t"),
+            max_errors: Some(0),
+            ..Default::default()
+        };
+
+        // no error is tolerated
+        match csv2bibtex::run(&config) {
+            Err(e @ csv2bibtex::Error::TooManyErrors { .. }) => assert_eq!(e.exit_code(), 7),
+            x => panic!("Unexpected result: {:?}", x),
+        }
+
+        // two rows of this file are too short, the others only have an empty cell at the end
+        config.max_errors = Some(2);
+        let summary = csv2bibtex::run(&config).unwrap();
+        assert_eq!(
+            (summary.rows, summary.entries, summary.rejected),
+            (10, 8, 2)
+        );
+
+        // too high error rate
+        config.max_error_rate = Some(0.1);
+        match csv2bibtex::run(&config) {
+            Err(csv2bibtex::Error::TooManyErrors { errors, rows }) => {
+                assert_eq!((errors, rows), (2, 10))
+            }
+            x => panic!("Unexpected result: {:?}", x),
+        }
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test2-output3.bib").unwrap();
+    }
 }
@@ -8,7 +8,7 @@ mod test_input3 {
     fn test_verbatim_fields() {
         // build config structure
         let mut config = csv2bibtex::args::Config {
-            file_input: std::path::PathBuf::from("./tests/test3-input1.csv"),
+            files_input: vec![std::path::PathBuf::from("./tests/test3-input1.csv")],
             file_output: std::path::PathBuf::from("./tests/tmp-test3-output1.bib"),
             csv_delimiter: String::from(";"),
             csv_lazy: true,
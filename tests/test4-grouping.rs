@@ -0,0 +1,76 @@
+//! Test merging of several rows into one entry
+
+#[cfg(test)]
+mod test_input4 {
+    fn config(output: &str) -> csv2bibtex::args::Config {
+        let mut config = csv2bibtex::args::Config {
+            files_input: vec![std::path::PathBuf::from("./tests/test4-input1.csv")],
+            file_output: std::path::PathBuf::from(output),
+            mapping_defaults: false,
+            group_by: Some(String::from("ID")),
+            ..Default::default()
+        };
+
+        // build field hash map
+        config
+            .csv_field_mapping
+            .insert(String::from("bibtexkey"), String::from("[[ID]]"));
+        config
+            .csv_field_mapping
+            .insert(String::from("author"), String::from("[[Author]]"));
+        config
+            .csv_field_mapping
+            .insert(String::from("title"), String::from("[[Title]]"));
+        config
+            .csv_field_mapping
+            .insert(String::from("keywords"), String::from("[[Keyword]]"));
+        config
+            .csv_field_mapping
+            .insert(String::from("year"), String::from("[[Year]]"));
+
+        config
+    }
+
+    #[test]
+    fn group_all_rows() {
+        let config = config("./tests/tmp-test4-output1.bib");
+
+        // run main function
+        let summary = csv2bibtex::run(&config).unwrap();
+        assert_eq!((summary.rows, summary.entries), (5, 2));
+
+        // compare our output with expected output
+        let left = biblatex::Bibliography::parse(
+            &std::fs::read_to_string("./tests/test4-output1.bib").unwrap(),
+        )
+        .unwrap();
+        let right = biblatex::Bibliography::parse(
+            &std::fs::read_to_string("./tests/tmp-test4-output1.bib").unwrap(),
+        )
+        .unwrap();
+        assert!(left.iter().eq(right.iter()));
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test4-output1.bib").unwrap();
+    }
+
+    #[test]
+    fn group_consecutive_rows() {
+        let mut config = config("./tests/tmp-test4-output2.bib");
+        config.group_consecutive = true;
+        config.aggregations.insert(
+            String::from("keywords"),
+            csv2bibtex::group::Aggregation::Join(String::from("; ")),
+        );
+
+        // run main function
+        let summary = csv2bibtex::run(&config).unwrap();
+        assert_eq!((summary.rows, summary.entries), (5, 4));
+
+        let output = std::fs::read_to_string("./tests/tmp-test4-output2.bib").unwrap();
+        assert!(output.contains("keywords = {heart sound; smart phone},"));
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test4-output2.bib").unwrap();
+    }
+}
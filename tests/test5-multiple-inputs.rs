@@ -0,0 +1,137 @@
+//! Test the conversion of several input files into one output file
+
+#[cfg(test)]
+mod test_input5 {
+    #[test]
+    fn multiple_inputs() {
+        // build config structure, the second file is given as glob pattern
+        let mut config = csv2bibtex::args::Config {
+            files_input: vec![
+                std::path::PathBuf::from("./tests/test4-input1.csv"),
+                std::path::PathBuf::from("./tests/test[4]-input*.csv"),
+            ],
+            file_output: std::path::PathBuf::from("./tests/tmp-test5-output1.bib"),
+            file_rejects: Some(std::path::PathBuf::from("./tests/tmp-test5-rejects1.csv")),
+            mapping_defaults: false,
+            source_field: Some(String::from("source")),
+            limit_rows: Some(8),
+            ..Default::default()
+        };
+
+        // build field hash map
+        config
+            .csv_field_mapping
+            .insert(String::from("bibtexkey"), String::from("[[ID]]"));
+        config
+            .csv_field_mapping
+            .insert(String::from("author"), String::from("[[Author]]"));
+
+        // run main function
+        let summary = csv2bibtex::run(&config).unwrap();
+        assert_eq!((summary.rows, summary.entries), (8, 8));
+
+        // keys are unique across both files
+        let output = std::fs::read_to_string("./tests/tmp-test5-output1.bib").unwrap();
+        let bibliography = biblatex::Bibliography::parse(&output).unwrap();
+        let keys: Vec<&str> = bibliography.iter().map(|x| x.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec!["r1", "r1_2", "r2", "r1_3", "r2_2", "r1_4", "r1_5", "r2_3"]
+        );
+        assert!(output.contains("source = {./tests/test4-input1.csv},"));
+
+        // the rejects file has a column for the input file
+        let rejects = std::fs::read_to_string("./tests/tmp-test5-rejects1.csv").unwrap();
+        assert_eq!(
+            rejects,
+            "file,line,byte,error,ID,Author,Title,Keyword,Year\n"
+        );
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test5-output1.bib").unwrap();
+        std::fs::remove_file("./tests/tmp-test5-rejects1.csv").unwrap();
+    }
+
+    #[test]
+    fn output_is_input() {
+        // the output file is matched by the glob pattern of the input files
+        std::fs::copy("./tests/test4-input1.csv", "./tests/tmp-test5-input2.csv").unwrap();
+        let config = csv2bibtex::args::Config {
+            files_input: vec![std::path::PathBuf::from("./tests/tmp-test5-input[2].csv")],
+            file_output: std::path::PathBuf::from("./tests/tmp-test5-input2.csv"),
+            ..Default::default()
+        };
+        assert!(matches!(
+            csv2bibtex::run(&config),
+            Err(csv2bibtex::Error::Config(_))
+        ));
+
+        // the input file is left as it was
+        assert_eq!(
+            std::fs::read_to_string("./tests/tmp-test5-input2.csv").unwrap(),
+            std::fs::read_to_string("./tests/test4-input1.csv").unwrap()
+        );
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test5-input2.csv").unwrap();
+    }
+
+    #[test]
+    fn suggested_mapping() {
+        // the mapping is guessed from the first file, the second file lacks the year
+        std::fs::write(
+            "./tests/tmp-test5-input3.csv",
+            "ID,Author,Title\nr9,\"Doe, J.\",Another title\n",
+        )
+        .unwrap();
+        let config = csv2bibtex::args::Config {
+            files_input: vec![
+                std::path::PathBuf::from("./tests/test4-input1.csv"),
+                std::path::PathBuf::from("./tests/tmp-test5-input3.csv"),
+            ],
+            file_output: std::path::PathBuf::from("./tests/tmp-test5-output3.bib"),
+            mapping_defaults: false,
+            suggest_mapping: true,
+            ..Default::default()
+        };
+
+        // run main function, guessed columns don't have to exist in every file
+        let summary = csv2bibtex::run(&config).unwrap();
+        assert_eq!(summary.entries, summary.rows);
+
+        let output = std::fs::read_to_string("./tests/tmp-test5-output3.bib").unwrap();
+        assert!(output.contains("date = {2014},"));
+        assert!(output.contains("title = {Another title},"));
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test5-input3.csv").unwrap();
+        std::fs::remove_file("./tests/tmp-test5-output3.bib").unwrap();
+    }
+
+    #[test]
+    fn rejects_with_different_columns() {
+        // the rows of both files cannot be written to one rejects file
+        std::fs::write(
+            "./tests/tmp-test5-input4.csv",
+            "ID,Title\nr9,Another title\n",
+        )
+        .unwrap();
+        let config = csv2bibtex::args::Config {
+            files_input: vec![
+                std::path::PathBuf::from("./tests/test4-input1.csv"),
+                std::path::PathBuf::from("./tests/tmp-test5-input4.csv"),
+            ],
+            file_output: std::path::PathBuf::from("./tests/tmp-test5-output4.bib"),
+            file_rejects: Some(std::path::PathBuf::from("./tests/tmp-test5-rejects4.csv")),
+            ..Default::default()
+        };
+        assert!(matches!(
+            csv2bibtex::run(&config),
+            Err(csv2bibtex::Error::Config(_))
+        ));
+        assert!(!std::path::Path::new("./tests/tmp-test5-output4.bib").exists());
+
+        // clean up
+        std::fs::remove_file("./tests/tmp-test5-input4.csv").unwrap();
+    }
+}